@@ -0,0 +1,173 @@
+/// tests for this crate
+#[cfg(test)]
+mod tests {
+	use super::super::*;
+
+	use price::Attestation;
+	use primitives::{sr25519, Pair};
+	use schnorrkel::musig::{Cosignature, Reveal};
+	use schnorrkel::{ExpansionMode, Keypair, MiniSecretKey, PublicKey};
+	use sr_primitives::{AnySignature, Perbill};
+
+	type AccountId = sr25519::Public;
+	type Signature = AnySignature;
+
+	fn pair(seed: &str) -> sr25519::Pair {
+		sr25519::Pair::from_string(&format!("//{}", seed), None).unwrap()
+	}
+
+	fn keypair(seed: u8) -> Keypair {
+		MiniSecretKey::from_bytes(&[seed; 32]).unwrap().expand_to_keypair(ExpansionMode::Ed25519)
+	}
+
+	fn keypairs() -> Vec<Keypair> {
+		(1..=3).map(keypair).collect()
+	}
+
+	fn oracles(keypairs: &[Keypair]) -> Vec<PublicKey> {
+		keypairs.iter().map(|k| k.public).collect()
+	}
+
+	fn attestation(price: price::Price) -> Attestation {
+		Attestation { feed: 0, price, round: 1 }
+	}
+
+	/// Run the MuSig rounds between `signers`, returning the reveal and cosignature of each.
+	fn session(builder: &AggregateSignatureBuilder, signers: &[&Keypair]) -> Vec<(Reveal, Cosignature)> {
+		let transcript = musig_transcript(builder.attestation());
+		let mut commits: Vec<_> = signers.iter().map(|k| k.musig(transcript.clone())).collect();
+		let commitments: Vec<_> = commits.iter().map(|m| m.our_commitment()).collect();
+		for (i, musig) in commits.iter_mut().enumerate() {
+			for (j, signer) in signers.iter().enumerate() {
+				if i != j {
+					musig.add_their_commitment(signer.public, commitments[j]).unwrap();
+				}
+			}
+		}
+
+		let mut reveals: Vec<_> = commits.into_iter().map(|m| m.reveal_stage()).collect();
+		let revealed: Vec<Reveal> = reveals.iter().map(|m| m.our_reveal().clone()).collect();
+		for (i, musig) in reveals.iter_mut().enumerate() {
+			for (j, signer) in signers.iter().enumerate() {
+				if i != j {
+					musig.add_their_reveal(signer.public, revealed[j].clone()).unwrap();
+				}
+			}
+		}
+
+		reveals
+			.into_iter()
+			.zip(revealed)
+			.map(|(m, reveal)| (reveal, m.cosign_stage().our_cosignature()))
+			.collect()
+	}
+
+	fn cosign(builder: &mut AggregateSignatureBuilder, signers: &[&Keypair]) {
+		for ((reveal, cosignature), signer) in session(builder, signers).into_iter().zip(signers) {
+			builder.add(&signer.public, reveal, cosignature).unwrap();
+		}
+	}
+
+	fn verify_aggregate(
+		attestation: &Attestation,
+		signature: &price::AggregateSignature<primitives::H512>,
+		keypairs: &[Keypair],
+		required: usize,
+	) -> bool {
+		let accounts: Vec<AccountId> = keypairs
+			.iter()
+			.map(|k| sr25519::Public::from_raw(k.public.to_bytes()))
+			.collect();
+		signature
+			.verify::<_, price::Sr25519MuSig>(&accounts, &attestation.signing_payload(), required)
+			.is_ok()
+	}
+
+	#[test]
+	fn signed_attestation_verifies() {
+		let signed: price::SignedAttestation<AccountId, Signature> = sign(&pair("Alice"), 0, 100, 1);
+		assert!(verify(&signed));
+
+		let mut tampered = signed.clone();
+		tampered.attestation.price = 101;
+		assert!(!verify(&tampered));
+	}
+
+	#[test]
+	fn bundle_keeps_latest_round() {
+		let mut bundle = Bundle::<AccountId, Signature>::new();
+		bundle.push(sign(&pair("Alice"), 0, 100, 2));
+		bundle.push(sign(&pair("Alice"), 0, 99, 1));
+		bundle.push(sign(&pair("Bob"), 0, 101, 1));
+		assert_eq!(bundle.len(), 2);
+
+		let inner = bundle.into_inner();
+		assert_eq!(inner[0].attestation.round, 2);
+		assert_eq!(inner[0].attestation.price, 100);
+	}
+
+	#[test]
+	fn aggregate_signature_verifies() {
+		let keypairs = keypairs();
+		let mut builder = AggregateSignatureBuilder::new(attestation(100), oracles(&keypairs));
+		cosign(&mut builder, &[&keypairs[2], &keypairs[0]]);
+		// 67% of 3 oracles is rounded up to all of them
+		assert!(!builder.is_complete(Perbill::from_percent(67)));
+		assert!(builder.is_complete(Perbill::from_percent(66)));
+
+		let (attestation, signature) = builder.build().unwrap();
+		// signers are sorted by their index in the oracle set
+		assert_eq!(signature.signers, vec![0, 2]);
+		assert!(verify_aggregate(&attestation, &signature, &keypairs, 2));
+		assert!(!verify_aggregate(&attestation, &signature, &keypairs, 3));
+
+		let mut builder = AggregateSignatureBuilder::new(attestation.clone(), oracles(&keypairs));
+		cosign(&mut builder, &[&keypairs[0], &keypairs[1], &keypairs[2]]);
+		assert!(builder.is_complete(Perbill::from_percent(67)));
+		let (_, all) = builder.build().unwrap();
+		// one signature whatever the count of signers
+		assert_eq!(all.signature.as_bytes().len(), signature.signature.as_bytes().len());
+		assert!(verify_aggregate(&attestation, &all, &keypairs, 3));
+	}
+
+	#[test]
+	fn aggregate_signature_rejects_tampered_price() {
+		let keypairs = keypairs();
+		let mut builder = AggregateSignatureBuilder::new(attestation(100), oracles(&keypairs));
+		cosign(&mut builder, &[&keypairs[0], &keypairs[1]]);
+
+		let (_, signature) = builder.build().unwrap();
+		assert!(!verify_aggregate(&attestation(101), &signature, &keypairs, 2));
+	}
+
+	#[test]
+	fn aggregate_signature_rejects_other_signers() {
+		let keypairs = keypairs();
+		let mut builder = AggregateSignatureBuilder::new(attestation(100), oracles(&keypairs));
+		cosign(&mut builder, &[&keypairs[0], &keypairs[1]]);
+
+		let (attestation, mut signature) = builder.build().unwrap();
+		signature.signers = vec![0, 1, 2];
+		assert!(!verify_aggregate(&attestation, &signature, &keypairs, 2));
+		signature.signers = vec![0, 2];
+		assert!(!verify_aggregate(&attestation, &signature, &keypairs, 2));
+		signature.signers = vec![0, 0, 1];
+		assert!(!verify_aggregate(&attestation, &signature, &keypairs, 2));
+	}
+
+	#[test]
+	fn aggregate_signature_rejects_non_oracles() {
+		let keypairs = keypairs();
+		let dave = keypair(4);
+		let mut builder = AggregateSignatureBuilder::new(attestation(100), oracles(&keypairs));
+		cosign(&mut builder, &[&keypairs[0]]);
+		assert_eq!(builder.len(), 1);
+
+		let mut cosigned = session(&builder, &[&keypairs[0], &dave]);
+		let (reveal, cosignature) = cosigned.remove(1);
+		assert_eq!(builder.add(&dave.public, reveal, cosignature), Err("signer is not an oracle"));
+		let (reveal, cosignature) = cosigned.remove(0);
+		assert_eq!(builder.add(&keypairs[0].public, reveal, cosignature), Err("oracle already signed"));
+		assert_eq!(builder.len(), 1);
+	}
+}
@@ -0,0 +1,96 @@
+//! Off-chain helpers for oracles to sign price attestations, and for relayers to bundle them
+//! into one `price::submit_aggregate` call, or into one MuSig aggregate signature for
+//! `price::submit_threshold`.
+
+use price::{Attestation, FeedId, Price, Round, SignedAttestation};
+use primitives::Pair;
+use sr_primitives::traits::Verify;
+
+pub mod threshold;
+
+pub use threshold::{musig_transcript, AggregateSignatureBuilder};
+
+#[cfg(test)]
+mod aggregator_test;
+
+/// Sign a price attestation with an oracle's key pair.
+/// `round` should be the chain's current round, later rounds are rejected as too early
+/// and earlier ones as outdated.
+pub fn sign<P, AccountId, Signature>(
+    pair: &P,
+    feed: FeedId,
+    price: Price,
+    round: Round,
+) -> SignedAttestation<AccountId, Signature>
+where
+    P: Pair,
+    AccountId: From<P::Public>,
+    Signature: From<P::Signature>,
+{
+    let attestation = Attestation { feed, price, round };
+    let signature = pair.sign(&attestation.signing_payload());
+    SignedAttestation {
+        attestation,
+        signer: pair.public().into(),
+        signature: signature.into(),
+    }
+}
+
+/// Predicate if an attestation is signed by its signer, as checked on chain.
+pub fn verify<AccountId, Signature>(signed: &SignedAttestation<AccountId, Signature>) -> bool
+where
+    Signature: Verify<Signer = AccountId>,
+{
+    signed
+        .signature
+        .verify(&signed.attestation.signing_payload()[..], &signed.signer)
+}
+
+/// Signed attestations collected for `price::submit_aggregate`.
+pub struct Bundle<AccountId, Signature> {
+    attestations: Vec<SignedAttestation<AccountId, Signature>>,
+}
+
+impl<AccountId: PartialEq, Signature> Bundle<AccountId, Signature> {
+    pub fn new() -> Self {
+        Bundle {
+            attestations: Vec::new(),
+        }
+    }
+
+    /// Add an attestation. Only the latest round of each oracle for a feed is kept,
+    /// since outdated rounds would make the whole bundle rejected.
+    pub fn push(&mut self, signed: SignedAttestation<AccountId, Signature>) {
+        let existing = self.attestations.iter_mut().find(|x| {
+            x.signer == signed.signer && x.attestation.feed == signed.attestation.feed
+        });
+
+        match existing {
+            Some(x) => {
+                if signed.attestation.round > x.attestation.round {
+                    *x = signed;
+                }
+            }
+            None => self.attestations.push(signed),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.attestations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.attestations.is_empty()
+    }
+
+    /// The attestations, ready to be passed to `submit_aggregate`.
+    pub fn into_inner(self) -> Vec<SignedAttestation<AccountId, Signature>> {
+        self.attestations
+    }
+}
+
+impl<AccountId: PartialEq, Signature> Default for Bundle<AccountId, Signature> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,94 @@
+//! Collect the MuSig session of a threshold of oracles over one agreed price into an
+//! `AggregateSignature`, as verified by `price::submit_threshold`.
+//!
+//! Each signing oracle runs the MuSig rounds over `musig_transcript(attestation)` with the
+//! other signers, i.e. `keypair.musig(transcript)`, exchanging commitments, then reveals, then
+//! cosignatures. The relayer adds each signer's reveal and cosignature to the builder.
+
+use price::{required_signers, AggregateSignature, Attestation, MUSIG_CONTEXT};
+use primitives::H512;
+use schnorrkel::context::SigningTranscript;
+use schnorrkel::musig::{collect_cosignatures, Cosignature, Reveal};
+use schnorrkel::{signing_context, PublicKey};
+use sr_primitives::Perbill;
+
+/// The transcript oracles run their MuSig session on to sign `attestation`.
+pub fn musig_transcript(attestation: &Attestation) -> impl SigningTranscript + Clone {
+    signing_context(MUSIG_CONTEXT).bytes(&attestation.signing_payload())
+}
+
+/// Reveals and cosignatures of oracles signing the same attestation, collected off chain.
+pub struct AggregateSignatureBuilder {
+    attestation: Attestation,
+    oracles: Vec<PublicKey>,
+    cosigners: Vec<(u32, Reveal, Cosignature)>,
+}
+
+impl AggregateSignatureBuilder {
+    /// Start collecting the MuSig session over `attestation` of the acting `oracles`,
+    /// in the same order as stored on chain.
+    pub fn new(attestation: Attestation, oracles: Vec<PublicKey>) -> Self {
+        AggregateSignatureBuilder {
+            attestation,
+            oracles,
+            cosigners: Vec::new(),
+        }
+    }
+
+    pub fn attestation(&self) -> &Attestation {
+        &self.attestation
+    }
+
+    /// Add the reveal and cosignature of one oracle of the session.
+    pub fn add(
+        &mut self,
+        signer: &PublicKey,
+        reveal: Reveal,
+        cosignature: Cosignature,
+    ) -> Result<(), &'static str> {
+        let index = self
+            .oracles
+            .iter()
+            .position(|x| x == signer)
+            .ok_or("signer is not an oracle")? as u32;
+        if self.cosigners.iter().any(|(i, _, _)| *i == index) {
+            return Err("oracle already signed");
+        }
+
+        self.cosigners.push((index, reveal, cosignature));
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.cosigners.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cosigners.is_empty()
+    }
+
+    /// Predicate if enough oracles signed for the given threshold, rounded up as on chain.
+    pub fn is_complete(&self, threshold: Perbill) -> bool {
+        self.cosigners.len() >= required_signers(threshold, self.oracles.len())
+    }
+
+    /// The aggregate signature, ready to be passed to `submit_threshold` with the attestation.
+    /// Every oracle of the session should have been added.
+    pub fn build(self) -> Result<(Attestation, AggregateSignature<H512>), &'static str> {
+        let mut collector = collect_cosignatures(musig_transcript(&self.attestation));
+        for (index, reveal, cosignature) in &self.cosigners {
+            let signer = self.oracles[*index as usize];
+            collector
+                .add(signer, reveal.clone(), *cosignature)
+                .map_err(|_| "bad cosignature")?;
+        }
+        let signature = H512::from(collector.signature().to_bytes());
+
+        let mut signers: Vec<u32> = self.cosigners.iter().map(|(index, _, _)| *index).collect();
+        signers.sort();
+        Ok((
+            self.attestation,
+            AggregateSignature { signers, signature },
+        ))
+    }
+}
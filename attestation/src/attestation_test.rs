@@ -0,0 +1,232 @@
+/// tests for this module
+#[cfg(test)]
+mod tests {
+	use super::super::*;
+
+	use runtime_io::with_externalities;
+	use primitives::{H256, Blake2Hasher};
+	use support::{impl_outer_origin, assert_noop, assert_ok, parameter_types};
+	use sr_primitives::{traits::{BlakeTwo256, ConvertInto, IdentityLookup, OnFinalize}, testing::Header};
+	use sr_primitives::weights::Weight;
+	use sr_primitives::Perbill;
+
+	impl_outer_origin! {
+		pub enum Origin for Test {}
+	}
+
+	// For testing the module, we construct most of a mock runtime. This means
+	// first constructing a configuration type (`Test`) which `impl`s each of the
+	// configuration traits of modules we want to use.
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
+	}
+	impl system::Trait for Test {
+		type Origin = Origin;
+		type Call = ();
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type WeightMultiplierUpdate = ();
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+	}
+	parameter_types! {
+		pub const ExistentialDeposit: u64 = 0;
+		pub const TransferFee: u64 = 0;
+		pub const CreationFee: u64 = 0;
+		pub const TransactionBaseFee: u64 = 0;
+		pub const TransactionByteFee: u64 = 0;
+	}
+	impl balances::Trait for Test {
+		type Balance = u64;
+		type OnFreeBalanceZero = ();
+		type OnNewAccount = ();
+		type Event = ();
+		type TransactionPayment = ();
+		type DustRemoval = ();
+		type TransferPayment = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type TransferFee = TransferFee;
+		type CreationFee = CreationFee;
+		type TransactionBaseFee = TransactionBaseFee;
+		type TransactionByteFee = TransactionByteFee;
+		type WeightToFee = ConvertInto;
+	}
+
+	/// Accounts 1 to 5 are the acting oracles.
+	pub struct TestOracles;
+	impl OracleMixedIn<Test> for TestOracles {
+		fn on_witnessed(_: &u64, _: ServiceId, _: DutyId) {}
+		fn on_duty_added(_: ServiceId, _: DutyId) {}
+		fn is_valid(_: &u64, _: ServiceId, _: DutyId) -> bool { true }
+		fn on_missed(_: &u64, _: ServiceId, _: DutyId) {}
+		fn on_deviation(_: &u64, _: ServiceId, _: DutyId, _: u32) {}
+		fn on_accuracy(_: &u64, _: ServiceId, _: DutyId, _: Perbill) {}
+		fn oracles() -> Vec<u64> { vec![1, 2, 3, 4, 5] }
+		fn key_owner(_: &u64) -> Option<u64> { None }
+		fn oracle_key(who: &u64) -> u64 { *who }
+	}
+
+	parameter_types! {
+		pub const AttestationServiceId: ServiceId = 1;
+		pub const Threshold: u32 = 4;
+		pub const AnswerPeriod: u64 = 10;
+		pub const MaxDataLength: u32 = 8;
+		pub const MinBounty: u64 = 10;
+		pub const MaxOpenRequests: u32 = 2;
+	}
+	impl Trait for Test {
+		type Event = ();
+		type Currency = Balances;
+		type OracleMixedIn = TestOracles;
+		type ServiceId = AttestationServiceId;
+		type ReportOrigin = system::EnsureSigned<u64>;
+		type Threshold = Threshold;
+		type AnswerPeriod = AnswerPeriod;
+		type MaxDataLength = MaxDataLength;
+		type MinBounty = MinBounty;
+		type MaxOpenRequests = MaxOpenRequests;
+		type OnAnswer = ();
+	}
+	type Balances = balances::Module<Test>;
+	type Attestation = Module<Test>;
+
+	// This function basically just builds a genesis storage key/value store according to
+	// our desired mockup.
+	fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+		let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		balances::GenesisConfig::<Test> {
+			balances: vec![(1, 100), (2, 100), (3, 100), (4, 100), (5, 100), (10, 1000)],
+			vesting: vec![],
+		}.assimilate_storage(&mut t).unwrap();
+		t.into()
+	}
+
+	fn answer_all(id: RequestId, answers: &[(u64, &[u8])]) {
+		for &(oracle, payload) in answers {
+			assert_ok!(Attestation::answer(Origin::signed(oracle), id, payload.to_vec()));
+		}
+	}
+
+	fn finalize(block: u64) {
+		system::Module::<Test>::set_block_number(block);
+		Attestation::on_finalize(block);
+	}
+
+	#[test]
+	fn settles_once_threshold_agrees() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Attestation::post_request(Origin::signed(10), b"spec".to_vec(), 90));
+			assert_eq!(Balances::reserved_balance(&10), 90);
+
+			answer_all(0, &[(1, b"a"), (2, b"a"), (3, b"b"), (4, b"a")]);
+			assert!(Attestation::request(0).is_some());
+
+			assert_ok!(Attestation::answer(Origin::signed(5), 0, b"a".to_vec()));
+			assert_eq!(Attestation::request(0), None);
+			assert_eq!(Attestation::answers(0), vec![]);
+			assert_eq!(Attestation::open_requests(), vec![]);
+			// The bounty is split among oracles that agreed, the remainder is refunded.
+			for &oracle in &[1, 2, 4, 5] {
+				assert_eq!(Balances::free_balance(&oracle), 122);
+			}
+			assert_eq!(Balances::free_balance(&3), 100);
+			assert_eq!(Balances::reserved_balance(&10), 0);
+			assert_eq!(Balances::free_balance(&10), 912);
+		});
+	}
+
+	#[test]
+	fn rejects_duplicate_and_late_answers() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Attestation::post_request(Origin::signed(10), b"spec".to_vec(), 90));
+			assert_ok!(Attestation::answer(Origin::signed(1), 0, b"a".to_vec()));
+			assert_noop!(
+				Attestation::answer(Origin::signed(1), 0, b"b".to_vec()),
+				"request already answered by this oracle"
+			);
+			assert_noop!(
+				Attestation::answer(Origin::signed(2), 0, b"too long payload".to_vec()),
+				"answer payload is too long"
+			);
+
+			system::Module::<Test>::set_block_number(11);
+			assert_noop!(Attestation::answer(Origin::signed(2), 0, b"a".to_vec()), "request has expired");
+			assert_noop!(Attestation::answer(Origin::signed(2), 1, b"a".to_vec()), "request does not exist");
+		});
+	}
+
+	#[test]
+	fn requests_need_a_bounty_and_are_capped() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_noop!(
+				Attestation::post_request(Origin::signed(10), b"spec".to_vec(), 9),
+				"bounty is too small"
+			);
+			assert_ok!(Attestation::post_request(Origin::signed(10), b"spec".to_vec(), 10));
+			assert_ok!(Attestation::post_request(Origin::signed(10), b"spec".to_vec(), 10));
+			assert_noop!(
+				Attestation::post_request(Origin::signed(10), b"spec".to_vec(), 10),
+				"too many open requests"
+			);
+			assert_eq!(Balances::reserved_balance(&10), 20);
+
+			// Room is made once open requests are settled or expired.
+			finalize(11);
+			assert_ok!(Attestation::post_request(Origin::signed(10), b"spec".to_vec(), 10));
+			assert_eq!(Attestation::open_requests(), vec![2]);
+			assert_eq!(Balances::reserved_balance(&10), 10);
+		});
+	}
+
+	#[test]
+	fn falls_back_to_majority_of_oracles_at_deadline() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Attestation::post_request(Origin::signed(10), b"spec".to_vec(), 90));
+			answer_all(0, &[(1, b"a"), (2, b"a"), (3, b"a"), (4, b"b")]);
+
+			finalize(10);
+			assert!(Attestation::request(0).is_some());
+
+			finalize(11);
+			assert_eq!(Attestation::request(0), None);
+			for &oracle in &[1, 2, 3] {
+				assert_eq!(Balances::free_balance(&oracle), 130);
+			}
+			assert_eq!(Balances::free_balance(&4), 100);
+			assert_eq!(Balances::free_balance(&10), 910);
+		});
+	}
+
+	#[test]
+	fn expires_without_majority_of_oracles_and_refunds() {
+		with_externalities(&mut new_test_ext(), || {
+			// A single answer, or a majority of the answers only, doesn't settle a request.
+			assert_ok!(Attestation::post_request(Origin::signed(10), b"spec".to_vec(), 90));
+			answer_all(0, &[(1, b"a")]);
+			assert_ok!(Attestation::post_request(Origin::signed(10), b"spec".to_vec(), 60));
+			answer_all(1, &[(1, b"a"), (2, b"a"), (3, b"b")]);
+
+			finalize(11);
+			assert_eq!(Attestation::request(0), None);
+			assert_eq!(Attestation::request(1), None);
+			assert_eq!(Attestation::open_requests(), vec![]);
+			assert_eq!(Balances::free_balance(&1), 100);
+			assert_eq!(Balances::reserved_balance(&10), 0);
+			assert_eq!(Balances::free_balance(&10), 1000);
+		});
+	}
+}
@@ -0,0 +1,274 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use oracle::{DutyId, OracleMixedIn, ServiceId};
+use rstd::prelude::*;
+use sr_primitives::traits::{EnsureOrigin, Saturating, Zero};
+use support::traits::{Currency, Get, ReservableCurrency};
+use support::{decl_event, decl_module, decl_storage, dispatch::Result, StorageMap, StorageValue};
+use system::ensure_signed;
+
+#[cfg(test)]
+mod attestation_test;
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+/// Identifier of an attestation request.
+pub type RequestId = u64;
+
+/// The duty of answering attestation requests, as tracked by the oracle module.
+/// It's not declared mandatory, answers are paid by request bounties instead.
+pub const ATTESTATION_DUTY: DutyId = 0;
+
+pub trait Trait: system::Trait {
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+    /// Currency type of bounties.
+    type Currency: ReservableCurrency<Self::AccountId>;
+
+    type OracleMixedIn: OracleMixedIn<Self>;
+    /// The service id this module is registered with in the oracle module.
+    type ServiceId: Get<ServiceId>;
+    type ReportOrigin: EnsureOrigin<Self::Origin, Success = Self::AccountId>;
+
+    /// The count of identical answers that settles a request.
+    type Threshold: Get<u32>;
+
+    /// The duration in which oracles should answer a request.
+    type AnswerPeriod: Get<Self::BlockNumber>;
+
+    /// The maximum length of a query spec or an answer payload.
+    type MaxDataLength: Get<u32>;
+
+    /// The minimum bounty of a request.
+    type MinBounty: Get<BalanceOf<Self>>;
+
+    /// The maximum count of open requests.
+    type MaxOpenRequests: Get<u32>;
+
+    /// The consumer notified when a request is settled or expired.
+    type OnAnswer: OnAnswer<Self::AccountId>;
+}
+
+/// Consumer module should implement this trait to receive answers.
+pub trait OnAnswer<AccountId> {
+    /// A request is settled with the agreed payload.
+    fn on_answer(id: RequestId, requester: &AccountId, payload: &[u8]);
+    /// A request expired without agreement, its bounty is refunded.
+    fn on_expired(id: RequestId, requester: &AccountId);
+}
+
+impl<AccountId> OnAnswer<AccountId> for () {
+    fn on_answer(_: RequestId, _: &AccountId, _: &[u8]) {}
+    fn on_expired(_: RequestId, _: &AccountId) {}
+}
+
+/// An attestation request posted by a consumer.
+#[derive(PartialEq, Eq, Clone, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Request<AccountId, Balance, BlockNumber> {
+    requester: AccountId,
+    spec: Vec<u8>,
+    bounty: Balance,
+    deadline: BlockNumber,
+}
+
+/// One oracle's answer to a request, either the payload itself or its hash.
+#[derive(PartialEq, Eq, Clone, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Answer<AccountId> {
+    reporter: AccountId,
+    payload: Vec<u8>,
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as AttestationStorage {
+        /// Id of the next request.
+        NextRequestId get(next_request_id): RequestId;
+
+        /// Open requests.
+        Requests get(request): map RequestId => Option<Request<T::AccountId, BalanceOf<T>, T::BlockNumber>>;
+
+        /// Ids of open requests.
+        OpenRequests get(open_requests): Vec<RequestId>;
+
+        /// Answers submitted to open requests.
+        Answers get(answers): map RequestId => Vec<Answer<T::AccountId>>;
+    }
+}
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        fn deposit_event() = default;
+
+        const Threshold: u32 = T::Threshold::get();
+        const AnswerPeriod: T::BlockNumber = T::AnswerPeriod::get();
+        const MaxDataLength: u32 = T::MaxDataLength::get();
+        const MinBounty: BalanceOf<T> = T::MinBounty::get();
+        const MaxOpenRequests: u32 = T::MaxOpenRequests::get();
+
+        /// post a request with a query spec, reserving the bounty for oracles.
+        pub fn post_request(origin, spec: Vec<u8>, bounty: BalanceOf<T>) -> Result{
+            let who = ensure_signed(origin)?;
+            if spec.len() > T::MaxDataLength::get() as usize {
+                return Err("query spec is too long");
+            }
+            if bounty < T::MinBounty::get() {
+                return Err("bounty is too small");
+            }
+            // Open requests are all checked at the end of each block, so they're kept bounded.
+            if Self::open_requests().len() >= T::MaxOpenRequests::get() as usize {
+                return Err("too many open requests");
+            }
+
+            T::Currency::reserve(&who, bounty)?;
+
+            let id = Self::next_request_id();
+            NextRequestId::put(id + 1);
+            <Requests<T>>::insert(id, Request {
+                requester: who.clone(),
+                spec: spec,
+                bounty: bounty,
+                deadline: <system::Module<T>>::block_number() + T::AnswerPeriod::get(),
+            });
+            OpenRequests::mutate(|ids| ids.push(id));
+
+            Self::deposit_event(RawEvent::RequestPosted(id, who, bounty));
+            Ok(())
+        }
+
+        /// answer an open request with a payload or its hash.
+        pub fn answer(origin, id: RequestId, payload: Vec<u8>) -> Result{
+            let who = T::ReportOrigin::ensure_origin(origin)?;
+            if payload.len() > T::MaxDataLength::get() as usize {
+                return Err("answer payload is too long");
+            }
+
+            let request = Self::request(id).ok_or("request does not exist")?;
+            if <system::Module<T>>::block_number() > request.deadline {
+                return Err("request has expired");
+            }
+
+            let mut answers = Self::answers(id);
+            if answers.iter().any(|a| a.reporter == who) {
+                return Err("request already answered by this oracle");
+            }
+            answers.push(Answer {
+                reporter: who.clone(),
+                payload: payload,
+            });
+
+            T::OracleMixedIn::on_witnessed(&who, T::ServiceId::get(), ATTESTATION_DUTY);
+            Self::deposit_event(RawEvent::RequestAnswered(id, who));
+
+            match Self::agreed_payload(&answers, T::Threshold::get() as usize) {
+                Some(payload) => Self::settle(id, request, answers, payload),
+                None => <Answers<T>>::insert(id, answers),
+            }
+            Ok(())
+        }
+
+        fn on_finalize() {
+            let block_number = <system::Module<T>>::block_number();
+            let (expired, open): (Vec<RequestId>, Vec<RequestId>) = Self::open_requests()
+                .into_iter()
+                .partition(|id| match Self::request(id) {
+                    Some(request) => block_number > request.deadline,
+                    None => true,
+                });
+            if expired.is_empty() {
+                return;
+            }
+            OpenRequests::put(open);
+
+            expired.into_iter().for_each(|id| {
+                let request = match Self::request(id) {
+                    Some(request) => request,
+                    None => return,
+                };
+                let answers = Self::answers(id);
+
+                // Fall back to a majority of acting oracles once the deadline has passed.
+                let majority = T::OracleMixedIn::oracles().len() / 2 + 1;
+                match Self::agreed_payload(&answers, majority) {
+                    Some(payload) => Self::settle(id, request, answers, payload),
+                    None => Self::expire(id, request),
+                }
+            });
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// The payload answered by at least `threshold` oracles, if any.
+    fn agreed_payload(answers: &[Answer<T::AccountId>], threshold: usize) -> Option<Vec<u8>> {
+        if threshold == 0 {
+            return None;
+        }
+
+        answers
+            .iter()
+            .find(|a| answers.iter().filter(|b| b.payload == a.payload).count() >= threshold)
+            .map(|a| a.payload.clone())
+    }
+
+    fn settle(
+        id: RequestId,
+        request: Request<T::AccountId, BalanceOf<T>, T::BlockNumber>,
+        answers: Vec<Answer<T::AccountId>>,
+        payload: Vec<u8>,
+    ) {
+        let winners: Vec<T::AccountId> = answers
+            .into_iter()
+            .filter(|a| a.payload == payload)
+            .map(|a| a.reporter)
+            .collect();
+
+        // Split the bounty among oracles that agreed, the remainder goes back to the requester.
+        let share = request.bounty / BalanceOf::<T>::from(winners.len() as u32);
+        let mut remaining = request.bounty;
+        if !share.is_zero() {
+            winners.iter().for_each(|w| {
+                if T::Currency::repatriate_reserved(&request.requester, w, share).is_ok() {
+                    remaining = remaining.saturating_sub(share);
+                }
+            });
+        }
+        T::Currency::unreserve(&request.requester, remaining);
+
+        Self::remove_request(id);
+        T::OnAnswer::on_answer(id, &request.requester, &payload);
+        Self::deposit_event(RawEvent::RequestSettled(id, payload));
+    }
+
+    fn expire(id: RequestId, request: Request<T::AccountId, BalanceOf<T>, T::BlockNumber>) {
+        T::Currency::unreserve(&request.requester, request.bounty);
+
+        Self::remove_request(id);
+        T::OnAnswer::on_expired(id, &request.requester);
+        Self::deposit_event(RawEvent::RequestExpired(id));
+    }
+
+    fn remove_request(id: RequestId) {
+        <Requests<T>>::remove(id);
+        <Answers<T>>::remove(id);
+        OpenRequests::mutate(|ids| ids.retain(|x| *x != id));
+    }
+}
+
+decl_event!(
+    pub enum Event<T>
+    where
+        AccountId = <T as system::Trait>::AccountId,
+        Balance = BalanceOf<T>,
+    {
+        /// Request posted by one account, with its bounty.
+        RequestPosted(RequestId, AccountId, Balance),
+        /// Request answered by one oracle.
+        RequestAnswered(RequestId, AccountId),
+        /// Request settled with the agreed payload.
+        RequestSettled(RequestId, Vec<u8>),
+        /// Request expired without agreement, bounty refunded.
+        RequestExpired(RequestId),
+    }
+);
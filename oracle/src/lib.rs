@@ -2,21 +2,53 @@
 #![feature(vec_remove_item)]
 
 use codec::{Decode, Encode};
+use primitives::crypto::KeyTypeId;
 use rstd::prelude::*;
-use sr_primitives::traits::{Bounded, CheckedAdd, CheckedSub, EnsureOrigin, Zero};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sr_primitives::traits::{
+    Bounded, CheckedAdd, CheckedSub, EnsureOrigin, Saturating, SimpleArithmetic, Verify, Zero,
+};
+use sr_primitives::Perbill;
 use support::traits::{
     ChangeMembers, Currency, Get, LockIdentifier, LockableCurrency, ReservableCurrency,
     WithdrawReasons,
 };
-use support::{decl_event, decl_module, decl_storage, dispatch::Result, StorageMap, StorageValue};
+use support::{
+    decl_event, decl_module, decl_storage, dispatch::Result, Parameter, StorageMap, StorageValue,
+};
 use system::{ensure_root, ensure_signed};
 
+mod migration;
 #[cfg(test)]
 mod oracle_test;
 
 type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+/// Identifier of a business module sharing the oracle set.
+pub type ServiceId = u32;
+
+/// Identifier of a duty that oracles report on, e.g. one price feed.
+pub type DutyId = u32;
+
+/// Counter of elections, each starting a new era.
+pub type EraIndex = u32;
+
+/// Key type of oracle keys generated in a node's keystore. They're only checked on chain once
+/// registered by `set_oracle_key`.
+pub const ORACLE: KeyTypeId = KeyTypeId(*b"orac");
+
+/// Public key of an oracle key generated in a node's keystore.
+pub type OracleId = primitives::sr25519::Public;
+
 const LockedId: LockIdentifier = *b"oracle  ";
 
+/// Context prepended to the controller account signed by an oracle key, proving it's owned.
+pub const ORACLE_KEY_CONTEXT: &[u8] = b"oracle-key";
+
+/// Scale of the decayed slash count in `Reputation`, i.e. one slash.
+pub const SLASH_UNIT: u32 = 1_000;
+
 pub trait Trait: system::Trait {
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
 
@@ -24,11 +56,14 @@ pub trait Trait: system::Trait {
     type Currency: LockableCurrency<Self::AccountId, Moment = Self::BlockNumber>
         + ReservableCurrency<Self::AccountId>;
 
-    /// The amount of fee that should be paid to each oracle during each reporting cycle.
-    type OracleFee: Get<BalanceOf<Self>>;
+    /// The maximum amount one oracle can be slashed for missed reports in an era.
+    type MaxSlashPerEra: Get<BalanceOf<Self>>;
 
-    /// The amount that'll be slashed if one oracle missed its reporting window.
-    type MissReportSlash: Get<BalanceOf<Self>>;
+    /// The count of consecutive missed windows after which an oracle is jailed.
+    type MaxConsecutiveMisses: Get<u32>;
+
+    /// The duration a jailed oracle should wait before it can unjail.
+    type JailCooldown: Get<Self::BlockNumber>;
 
     /// The minimum amount to stake for an oracle candidate.
     type MinStaking: Get<BalanceOf<Self>>;
@@ -36,6 +71,9 @@ pub trait Trait: system::Trait {
     /// The origin that's responsible for slashing malicious oracles.
     type MaliciousSlashOrigin: EnsureOrigin<Self::Origin>;
 
+    /// The origin that's able to set oracle parameters and services, besides root.
+    type ParametersOrigin: EnsureOrigin<Self::Origin>;
+
     /// The maxium count of working oracles.
     type Count: Get<u16>;
 
@@ -48,17 +86,232 @@ pub trait Trait: system::Trait {
     /// The locked time of staked amount.
     type LockedDuration: Get<Self::BlockNumber>;
 
+    /// The delay before a miss-report slash is applied, during which it can be cancelled.
+    type SlashDeferDuration: Get<Self::BlockNumber>;
+
     /// The actual oracle membership management type. (Usually the `srml_collective::Trait`)
     type ChangeMembers: ChangeMembers<Self::AccountId>;
+
+    /// The business modules declaring which duties are mandatory.
+    type Duties: OracleDuties;
+
+    /// The weight of a new sample in reputations, the rest decays exponentially.
+    type ReputationDecay: Get<Perbill>;
+
+    /// The weight of reputation next to stake in elections, zero to elect by stake only.
+    type ReputationWeight: Get<Perbill>;
+
+    /// How each reporting window's reward of a duty is split among oracles.
+    type RewardPolicy: RewardPolicy<Self::AccountId, BalanceOf<Self>>;
+
+    /// The count of past eras whose rewards can still be claimed.
+    type HistoryDepth: Get<EraIndex>;
+
+    /// The maximum count of unbonding chunks in a ledger.
+    type MaxUnlockingChunks: Get<u32>;
+
+    /// The count of blocks before an election at which candidates are snapshotted.
+    type SnapshotLead: Get<Self::BlockNumber>;
+
+    /// Signature type of oracle keys, signing their controller to prove they own the key.
+    type KeySignature: Verify<Signer = Self::AccountId> + Parameter;
+
+    /// The duty oracles reported in version 0, before services were introduced.
+    type LegacyDuty: Get<(ServiceId, DutyId)>;
+
+    /// Fee and slash of version 0, the service of `LegacyDuty` is registered with when migrating.
+    type LegacyServiceInfo: Get<ServiceInfo<BalanceOf<Self>>>;
+}
+
+/// The payload an oracle key should sign for `set_oracle_key` by `controller`.
+pub fn oracle_key_payload<AccountId: Encode>(controller: &AccountId) -> Vec<u8> {
+    (ORACLE_KEY_CONTEXT, controller).encode()
 }
 
 /// Business module should use this trait to
 /// communicate with oracle module in order to decouple them.
 pub trait OracleMixedIn<T: system::Trait> {
-    /// Tell oracle module that an event is reported by a speicifc oracle.
-    fn on_witnessed(who: &T::AccountId);
-    /// Predicate if one oracle is valid.
-    fn is_valid(who: &T::AccountId) -> bool;
+    /// Tell oracle module that an event of a service's duty is reported by a speicifc oracle.
+    fn on_witnessed(who: &T::AccountId, service: ServiceId, duty: DutyId);
+    /// Tell oracle module that a service's duty became mandatory, so acting oracles get a full
+    /// reporting window before it's missed.
+    fn on_duty_added(service: ServiceId, duty: DutyId);
+    /// Predicate if one oracle is valid for a service's duty.
+    fn is_valid(who: &T::AccountId, service: ServiceId, duty: DutyId) -> bool;
+    /// Tell oracle module that a specific oracle failed to report a service's duty,
+    /// counted at most once per reporting window.
+    fn on_missed(who: &T::AccountId, service: ServiceId, duty: DutyId);
+    /// Tell oracle module how far a report deviated from the aggregated value, in basis points.
+    fn on_deviation(who: &T::AccountId, service: ServiceId, duty: DutyId, deviation: u32);
+    /// Tell oracle module the accuracy of a specific oracle's last report of a service's duty.
+    fn on_accuracy(who: &T::AccountId, service: ServiceId, duty: DutyId, accuracy: Perbill);
+    /// Acting oracles.
+    fn oracles() -> Vec<T::AccountId>;
+    /// The oracle on behalf of which a key signs, if any.
+    fn key_owner(key: &T::AccountId) -> Option<T::AccountId>;
+    /// The key an oracle signs with, the oracle account itself if it has no key.
+    fn oracle_key(who: &T::AccountId) -> T::AccountId;
+}
+
+/// Business module should use this trait to
+/// declare the duties each oracle must report on.
+pub trait OracleDuties {
+    /// Duties, by service, that should be reported within each reporting cycle.
+    fn mandatory_duties() -> Vec<(ServiceId, DutyId)>;
+}
+
+impl OracleDuties for () {
+    fn mandatory_duties() -> Vec<(ServiceId, DutyId)> {
+        Vec::new()
+    }
+}
+
+macro_rules! impl_oracle_duties_for_tuples {
+    ($($t:ident),+) => {
+        impl<$($t: OracleDuties),+> OracleDuties for ($($t,)+) {
+            fn mandatory_duties() -> Vec<(ServiceId, DutyId)> {
+                let mut duties = Vec::new();
+                $(duties.extend($t::mandatory_duties());)+
+                duties
+            }
+        }
+    };
+}
+
+impl_oracle_duties_for_tuples!(A);
+impl_oracle_duties_for_tuples!(A, B);
+impl_oracle_duties_for_tuples!(A, B, C);
+impl_oracle_duties_for_tuples!(A, B, C, D);
+
+/// Runtime should choose a policy splitting the reward of a reporting window among oracles
+/// that reported on time.
+pub trait RewardPolicy<AccountId, Balance> {
+    /// Split `budget` among oracles, by the accuracy of their reports.
+    fn distribute(budget: Balance, accuracies: &[(AccountId, Perbill)]) -> Vec<(AccountId, Balance)>;
+}
+
+/// Split rewards equally, regardless of accuracy.
+pub struct EqualReward;
+
+impl<AccountId: Clone, Balance: SimpleArithmetic + Copy> RewardPolicy<AccountId, Balance> for EqualReward {
+    fn distribute(budget: Balance, accuracies: &[(AccountId, Perbill)]) -> Vec<(AccountId, Balance)> {
+        if accuracies.is_empty() {
+            return vec![];
+        }
+        let share = budget / Balance::from(accuracies.len() as u32);
+        accuracies.iter().map(|(who, _)| (who.clone(), share)).collect()
+    }
+}
+
+/// Split rewards in proportion to accuracy.
+pub struct AccuracyWeightedReward;
+
+impl<AccountId: Clone, Balance: SimpleArithmetic + Copy> RewardPolicy<AccountId, Balance>
+    for AccuracyWeightedReward
+{
+    fn distribute(budget: Balance, accuracies: &[(AccountId, Perbill)]) -> Vec<(AccountId, Balance)> {
+        let total: u64 = accuracies
+            .iter()
+            .map(|(_, accuracy)| accuracy.deconstruct() as u64)
+            .sum();
+        if total == 0 {
+            return vec![];
+        }
+        accuracies
+            .iter()
+            .map(|(who, accuracy)| {
+                let part = Perbill::from_rational_approximation(accuracy.deconstruct() as u64, total);
+                (who.clone(), part * budget)
+            })
+            .collect()
+    }
+}
+
+/// Fee and slash parameters of one service.
+#[derive(PartialEq, Eq, Clone, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub struct ServiceInfo<Balance> {
+    /// The amount of fee that should be paid to each oracle for each duty during each reporting cycle.
+    /// Fees of oracles that reported on time are pooled and split by the `RewardPolicy`.
+    pub fee: Balance,
+    /// The amount that'll be slashed if one oracle missed its reporting window of a duty.
+    /// It's multiplied by the number of consecutive missed windows.
+    pub miss_report_slash: Balance,
+    /// The maximum amount paid to all oracles for this service in an era.
+    pub reward_budget: Balance,
+}
+
+/// Oracle parameters adjustable by governance, the module's constants are used until they're set.
+#[derive(PartialEq, Eq, Clone, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct OracleParameters<Balance, BlockNumber> {
+    /// The minimum amount to stake for an oracle candidate.
+    pub min_staking: Balance,
+    /// The maxium count of working oracles.
+    pub count: u16,
+    /// The duration in which oracles should report and be paid.
+    pub report_interval: BlockNumber,
+    /// The duration between oracle elections, from the next scheduled one.
+    pub election_era: BlockNumber,
+    /// The locked time of staked amount, for amounts unbound afterwards.
+    pub locked_duration: BlockNumber,
+}
+
+/// Performance of one oracle aggregated over all services.
+#[derive(PartialEq, Eq, Clone, Default, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Performance<Balance> {
+    /// Count of duty reports.
+    reports: u32,
+    /// Count of missed reporting windows.
+    misses: u32,
+    /// Total amount paid.
+    paid: Balance,
+    /// Total amount slashed.
+    slashed: Balance,
+}
+
+/// Reputation of one oracle, with older samples decaying exponentially.
+#[derive(PartialEq, Eq, Clone, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Reputation {
+    /// Rate of reporting windows reported on time.
+    pub on_time: Perbill,
+    /// Average deviation of reports from the aggregated value, in basis points.
+    pub deviation: u32,
+    /// Count of slashes, scaled by `SLASH_UNIT`.
+    pub slashes: u32,
+}
+
+/// New oracles start at a neutral reputation, half way between a perfect and a failing record,
+/// so they neither outrank proven oracles nor are shut out of elections.
+impl Default for Reputation {
+    fn default() -> Self {
+        Reputation {
+            on_time: Perbill::from_percent(50),
+            deviation: 0,
+            slashes: 0,
+        }
+    }
+}
+
+impl Reputation {
+    /// Score between zero and one: on-time rate, lowered by deviation and slashes.
+    pub fn score(&self) -> Perbill {
+        let accuracy = Perbill::from_rational_approximation(
+            10_000u32.saturating_sub(self.deviation),
+            10_000u32,
+        );
+        let penalty =
+            Perbill::from_rational_approximation(SLASH_UNIT, SLASH_UNIT.saturating_add(self.slashes));
+        Perbill::from_parts(penalty * (accuracy * self.on_time.deconstruct()))
+    }
+}
+
+/// Move `old` towards `sample` by `weight`.
+fn decay(old: u32, sample: u32, weight: Perbill) -> u32 {
+    let keep = Perbill::from_parts(1_000_000_000 - weight.deconstruct());
+    (keep * old).saturating_add(weight * sample)
 }
 
 /// Unbind record for when an oracle is unbinding.
@@ -69,42 +322,163 @@ pub struct Unbind<Balance, BlockNumber> {
     era: BlockNumber,
 }
 
-/// The ledger of oracle's staked token.
+/// The ledger of oracle's staked token, kept by its controller.
 #[derive(PartialEq, Eq, Clone, Encode, Decode)]
 #[cfg_attr(feature = "std", derive(Debug))]
-pub struct Ledger<Balance: Default, BlockNumber> {
+pub struct Ledger<AccountId, Balance, BlockNumber> {
+    /// The stash account whose balance is bonded.
+    stash: AccountId,
     active: Balance,
     unbonds: Vec<Unbind<Balance, BlockNumber>>,
 }
 
-impl<Balance: Default, BlockNumber> Default for Ledger<Balance, BlockNumber> {
-    fn default() -> Self {
-        Ledger {
-            active: Balance::default(),
-            unbonds: vec![],
+impl<AccountId, Balance: SimpleArithmetic + Copy, BlockNumber> Ledger<AccountId, Balance, BlockNumber> {
+    /// Bonded amount, including the amount being unbonded.
+    fn total(&self) -> Balance {
+        self.unbonds
+            .iter()
+            .fold(self.active, |total, unbond| total + unbond.amount)
+    }
+}
+
+impl<AccountId, Balance: SimpleArithmetic + Copy, BlockNumber: PartialEq + Copy>
+    Ledger<AccountId, Balance, BlockNumber>
+{
+    /// Merge unbonds released at the same era into one chunk.
+    fn merge_unbonds(&mut self) {
+        let mut merged: Vec<Unbind<Balance, BlockNumber>> = Vec::with_capacity(self.unbonds.len());
+        for unbond in self.unbonds.drain(..) {
+            match merged.iter_mut().find(|x| x.era == unbond.era) {
+                Some(chunk) => chunk.amount += unbond.amount,
+                None => merged.push(unbond),
+            }
         }
+        self.unbonds = merged;
+    }
+}
+
+/// Where rewards of an oracle are paid.
+#[derive(PartialEq, Eq, Clone, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum RewardDestination<AccountId> {
+    /// Free balance of the stash.
+    Stash,
+    /// Free balance of the stash, and added to its bonded amount.
+    Staked,
+    /// Free balance of another account.
+    Account(AccountId),
+}
+
+impl<AccountId> Default for RewardDestination<AccountId> {
+    fn default() -> Self {
+        RewardDestination::Stash
     }
 }
 
+/// A slash that has been recorded but not yet applied.
+#[derive(PartialEq, Eq, Clone, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct UnappliedSlash<AccountId, Balance, BlockNumber> {
+    /// The stash to slash, which stays the same when its controller changes.
+    who: AccountId,
+    amount: Balance,
+    /// Start of the era whose `EraSlashed` cap the slash counts against.
+    era: BlockNumber,
+}
+
 decl_storage! {
     trait Store for Module<T: Trait> as OracleStorage {
         /// Acting oracles.
         Oracles get(oracles): Vec<T::AccountId>;
 
-        /// Staking ledger of oracle/candidates.
-        OracleLedger get(oracle_ledger): map T::AccountId => Ledger<BalanceOf<T>, T::BlockNumber>;
+        /// Controller of each bonded stash.
+        Bonded get(bonded): map T::AccountId => Option<T::AccountId>;
 
-        /// Blockstamp of each oracle's last event report.
-        WitnessReport get(witness_report): map T::AccountId => T::BlockNumber;
+        /// Where rewards of each stash are paid.
+        Payee get(payee): map T::AccountId => RewardDestination<T::AccountId>;
+
+        /// Staking ledger of oracle/candidates, keyed by controller.
+        OracleLedger get(oracle_ledger): map T::AccountId => Option<Ledger<T::AccountId, BalanceOf<T>, T::BlockNumber>>;
+
+        /// Registered services sharing the oracle set.
+        Services get(service): map ServiceId => Option<ServiceInfo<BalanceOf<T>>>;
+
+        /// Era and amount paid to oracles for each service.
+        ServicePaid get(service_paid): map ServiceId => (T::BlockNumber, BalanceOf<T>);
+
+        /// Blockstamp of each oracle's last event report of a service's duty.
+        WitnessReport get(witness_report): map (T::AccountId, ServiceId, DutyId) => T::BlockNumber;
 
         /// Oracle candidates.
         OracleCandidates get(candidates): Vec<T::AccountId>;
 
-        /// Current election era.
+        /// Height the current era started at.
         CurrentEra get(current_era): T::BlockNumber;
 
-        /// Oracle reward records.
-        OracleLastRewarded get(oracle_last_rewarded): map T::AccountId => T::BlockNumber;
+        /// Height of the next election, which ends the current era.
+        NextElectionAt get(next_election_at) build(|_| T::ElectionEra::get()): T::BlockNumber;
+
+        /// Index of the current era.
+        CurrentEraIndex get(current_era_index): EraIndex;
+
+        /// Rewards earned by each oracle stash in an era, to be claimed by `payout_era`.
+        ErasRewards get(eras_rewards): map (EraIndex, T::AccountId) => BalanceOf<T>;
+
+        /// Oracle stashes that earned rewards in an era.
+        ErasRewarded get(eras_rewarded): map EraIndex => Vec<T::AccountId>;
+
+        /// Total rewards earned by oracles in an era.
+        ErasTotalRewards get(eras_total_rewards): map EraIndex => BalanceOf<T>;
+
+        /// Oracle reward records of each service's duty.
+        OracleLastRewarded get(oracle_last_rewarded): map (T::AccountId, ServiceId, DutyId) => T::BlockNumber;
+
+        /// Accuracy of each oracle's last report of a service's duty, as told by the service.
+        Accuracy get(accuracy): map (T::AccountId, ServiceId, DutyId) => Option<Perbill>;
+
+        /// Consecutive reporting windows of a service's duty missed by each oracle.
+        ConsecutiveMisses get(consecutive_misses): map (T::AccountId, ServiceId, DutyId) => u32;
+
+        /// Blockstamp of each oracle's last missed reporting window of a service's duty.
+        LastMissed get(last_missed): map (T::AccountId, ServiceId, DutyId) => T::BlockNumber;
+
+        /// Performance of each oracle aggregated over all services.
+        OraclePerformance get(performance): map T::AccountId => Performance<BalanceOf<T>>;
+
+        /// Reputation of each oracle aggregated over all services.
+        Reputations get(reputation): map T::AccountId => Reputation;
+
+        /// Era and amount slashed for missed reports of each oracle stash.
+        EraSlashed get(era_slashed): map T::AccountId => (T::BlockNumber, BalanceOf<T>);
+
+        /// Jailed oracles, and the height after which they can unjail.
+        Jailed get(jailed): map T::AccountId => Option<T::BlockNumber>;
+
+        /// Pending slashes, keyed by the block height they'll be applied at.
+        UnappliedSlashes get(unapplied_slashes): map T::BlockNumber => Vec<UnappliedSlash<T::AccountId, BalanceOf<T>, T::BlockNumber>>;
+
+        /// Oracle key of each oracle, used to report on its behalf.
+        OracleKeys get(oracle_keys): map T::AccountId => Option<T::AccountId>;
+
+        /// Owner of each oracle key.
+        KeyOwner get(key_owner): map T::AccountId => Option<T::AccountId>;
+
+        /// Candidates of the next election and their weighted stakes, best first.
+        ElectionSnapshot get(election_snapshot): Option<Vec<(T::AccountId, BalanceOf<T>)>>;
+
+        /// Oracle parameters set by governance, overriding the module's constants.
+        ParameterOverrides get(parameter_overrides): Option<OracleParameters<BalanceOf<T>, T::BlockNumber>>;
+
+        /// Version of the storage layout, migrated in `on_initialize` after a runtime upgrade.
+        StorageVersion get(storage_version) build(|_| migration::STORAGE_VERSION): u32;
+    }
+    add_extra_genesis {
+        config(services): Vec<(ServiceId, ServiceInfo<BalanceOf<T>>)>;
+        build(|config: &GenesisConfig<T>| {
+            config.services.iter().for_each(|(service, info)| {
+                <Services<T>>::insert(service, info);
+            });
+        });
     }
 }
 
@@ -112,20 +486,144 @@ decl_module! {
     pub struct Module<T: Trait> for enum Call where origin: T::Origin {
         fn deposit_event() = default;
 
-        const OracleFee: BalanceOf<T> = T::OracleFee::get();
-        const MissReportSlash: BalanceOf<T> = T::MissReportSlash::get();
+        const MaxSlashPerEra: BalanceOf<T> = T::MaxSlashPerEra::get();
+        const MaxConsecutiveMisses: u32 = T::MaxConsecutiveMisses::get();
+        const JailCooldown: T::BlockNumber = T::JailCooldown::get();
         const MinStaking: BalanceOf<T> = T::MinStaking::get();
         const Count: u16 = T::Count::get();
         const ElectionEra: T::BlockNumber = T::ElectionEra::get();
         const ReportInteval: T::BlockNumber = T::ReportInteval::get();
         const LockedDuration: T::BlockNumber = T::LockedDuration::get();
+        const SlashDeferDuration: T::BlockNumber = T::SlashDeferDuration::get();
+        const ReputationDecay: Perbill = T::ReputationDecay::get();
+        const ReputationWeight: Perbill = T::ReputationWeight::get();
+        const HistoryDepth: EraIndex = T::HistoryDepth::get();
+        const MaxUnlockingChunks: u32 = T::MaxUnlockingChunks::get();
+        const SnapshotLead: T::BlockNumber = T::SnapshotLead::get();
+
+        fn on_initialize() {
+            if Self::storage_version() < migration::STORAGE_VERSION {
+                migration::migrate::<T>();
+            }
+        }
 
-        /// bind amount to list as oraclce candidates.
-        pub fn bid(origin, amount: BalanceOf<T>) -> Result{
-            let who = ensure_signed(origin)?;
+        /// bond amount of the sender as stash, managed by the controller.
+        pub fn bond(origin, controller: T::AccountId, value: BalanceOf<T>) -> Result{
+            let stash = ensure_signed(origin)?;
+            if <Bonded<T>>::exists(&stash) {
+                return Err("stash already bonded");
+            }
+            if <OracleLedger<T>>::exists(&controller) {
+                return Err("controller already paired");
+            }
+            if <KeyOwner<T>>::exists(&controller) || <KeyOwner<T>>::exists(&stash) {
+                return Err("account is an oracle key");
+            }
 
-            Self::bind(&who, amount)?;
-            Self::add_candidates(&who)?;
+            let value = rstd::cmp::min(value, T::Currency::free_balance(&stash));
+            if value < Self::parameters().min_staking {
+                return Err("staking amount is too small");
+            }
+
+            <Bonded<T>>::insert(&stash, &controller);
+            let ledger = Ledger {
+                stash: stash.clone(),
+                active: value,
+                unbonds: vec![],
+            };
+            Self::update_ledger(&controller, &ledger);
+            Self::deposit_event(RawEvent::OracleBonded(stash, value));
+            Ok(())
+        }
+
+        /// bond more amount of the sender's stash.
+        pub fn bond_extra(origin, max_additional: BalanceOf<T>) -> Result{
+            let stash = ensure_signed(origin)?;
+            let controller = Self::bonded(&stash).ok_or("not a stash")?;
+            let mut ledger = Self::oracle_ledger(&controller).ok_or("not a controller")?;
+
+            let free = T::Currency::free_balance(&stash);
+            let extra = rstd::cmp::min(max_additional, free.saturating_sub(ledger.total()));
+            ledger.active = ledger
+                .active
+                .checked_add(&extra)
+                .ok_or("Error calculating new staking")?;
+            Self::update_ledger(&controller, &ledger);
+            Self::deposit_event(RawEvent::OracleBonded(stash, extra));
+            Ok(())
+        }
+
+        /// list the sender's controller as oracle candidate.
+        pub fn bid(origin) -> Result{
+            let controller = ensure_signed(origin)?;
+            let ledger = Self::oracle_ledger(&controller).ok_or("not a controller")?;
+            if <Jailed<T>>::exists(&controller) {
+                return Err("oracle is jailed");
+            }
+            if ledger.active < Self::parameters().min_staking {
+                return Err("staking amount is too small");
+            }
+
+            Self::add_candidates(&controller)?;
+            Ok(())
+        }
+
+        /// stop being a candidate or an oracle, bonded amount stays bonded.
+        pub fn chill(origin) -> Result{
+            let controller = ensure_signed(origin)?;
+            if !<OracleLedger<T>>::exists(&controller) {
+                return Err("not a controller");
+            }
+
+            Self::remove_candidate(&controller);
+            Self::remove_oracle(&controller);
+            Self::deposit_event(RawEvent::OracleChilled(controller));
+            Ok(())
+        }
+
+        /// set where rewards of the sender's stash are paid, by its controller.
+        pub fn set_payee(origin, payee: RewardDestination<T::AccountId>) -> Result{
+            let controller = ensure_signed(origin)?;
+            let ledger = Self::oracle_ledger(&controller).ok_or("not a controller")?;
+            <Payee<T>>::insert(&ledger.stash, &payee);
+            Self::deposit_event(RawEvent::PayeeSet(ledger.stash, payee));
+            Ok(())
+        }
+
+        /// set the controller of the sender's stash.
+        pub fn set_controller(origin, controller: T::AccountId) -> Result{
+            let stash = ensure_signed(origin)?;
+            let old_controller = Self::bonded(&stash).ok_or("not a stash")?;
+            if <OracleLedger<T>>::exists(&controller) {
+                return Err("controller already paired");
+            }
+            if <KeyOwner<T>>::exists(&controller) {
+                return Err("account is an oracle key");
+            }
+            // Oracle records are kept by controller, so it can only change while chilled.
+            if Self::candidates().contains(&old_controller)
+                || Self::oracles().contains(&old_controller)
+                || <Jailed<T>>::exists(&old_controller)
+            {
+                return Err("controller should be chilled first");
+            }
+
+            if let Some(ledger) = <OracleLedger<T>>::take(&old_controller) {
+                <OracleLedger<T>>::insert(&controller, ledger);
+            }
+            if let Some(key) = <OracleKeys<T>>::take(&old_controller) {
+                <KeyOwner<T>>::insert(&key, &controller);
+                <OracleKeys<T>>::insert(&controller, key);
+            }
+            // The track record belongs to the stash, not to its current controller.
+            if <Reputations<T>>::exists(&old_controller) {
+                <Reputations<T>>::insert(&controller, <Reputations<T>>::take(&old_controller));
+            }
+            if <OraclePerformance<T>>::exists(&old_controller) {
+                <OraclePerformance<T>>::insert(&controller, <OraclePerformance<T>>::take(&old_controller));
+            }
+            <Bonded<T>>::insert(&stash, &controller);
+            Self::deposit_event(RawEvent::ControllerSet(stash, controller));
             Ok(())
         }
 
@@ -135,29 +633,267 @@ decl_module! {
                 .map(|_| ())
                 .or_else(ensure_root)
                 .map_err(|_| "bad origin")?;
-            T::Currency::slash(&who, amount);
-            Self::deposit_event(RawEvent::OracleSlashed(who, amount));
+            // Like other slashes, out of the bonded amount of the stash, keeping its ledger in sync.
+            let stash = Self::oracle_ledger(&who).map_or_else(|| who.clone(), |l| l.stash);
+            Self::slash(&stash, amount);
+            Ok(())
+        }
+
+        /// register or update a service sharing the oracle set, adjusting its fee and slash.
+        pub fn register_service(origin, service: ServiceId, info: ServiceInfo<BalanceOf<T>>) -> Result{
+            T::ParametersOrigin::try_origin(origin)
+                .map(|_| ())
+                .or_else(ensure_root)
+                .map_err(|_| "bad origin")?;
+            let is_new = !<Services<T>>::exists(service);
+            <Services<T>>::insert(service, info);
+            // Duties of a new service are only due from now on.
+            if is_new {
+                T::Duties::mandatory_duties()
+                    .into_iter()
+                    .filter(|&(s, _)| s == service)
+                    .for_each(|(_, duty)| Self::start_duty(service, duty));
+            }
+            Self::deposit_event(RawEvent::ServiceRegistered(service));
+            Ok(())
+        }
+
+        /// deregister a service, its duties will no longer be rewarded or slashed.
+        pub fn deregister_service(origin, service: ServiceId) -> Result{
+            T::ParametersOrigin::try_origin(origin)
+                .map(|_| ())
+                .or_else(ensure_root)
+                .map_err(|_| "bad origin")?;
+            if !<Services<T>>::exists(service) {
+                return Err("service is not registered");
+            }
+            <Services<T>>::remove(service);
+            <ServicePaid<T>>::remove(service);
+            Self::deposit_event(RawEvent::ServiceDeregistered(service));
             Ok(())
         }
 
-        /// unbind amount.
+        /// set oracle parameters, by root or `ParametersOrigin`.
+        pub fn set_parameters(origin, parameters: OracleParameters<BalanceOf<T>, T::BlockNumber>) -> Result{
+            T::ParametersOrigin::try_origin(origin)
+                .map(|_| ())
+                .or_else(ensure_root)
+                .map_err(|_| "bad origin")?;
+            if parameters.count == 0 {
+                return Err("oracle count is zero");
+            }
+            if parameters.report_interval.is_zero() || parameters.election_era.is_zero() {
+                return Err("duration is zero");
+            }
+
+            <ParameterOverrides<T>>::put(&parameters);
+            Self::deposit_event(RawEvent::ParametersSet(parameters));
+            Ok(())
+        }
+
+        /// cancel pending slashes that'll be applied at `apply_at`, by their indices.
+        pub fn cancel_deferred_slash(origin, apply_at: T::BlockNumber, slash_indices: Vec<u32>) -> Result{
+            T::MaliciousSlashOrigin::try_origin(origin)
+                .map(|_| ())
+                .or_else(ensure_root)
+                .map_err(|_| "bad origin")?;
+
+            let mut slash_indices = slash_indices;
+            slash_indices.sort_unstable();
+            slash_indices.dedup();
+
+            let mut unapplied = Self::unapplied_slashes(&apply_at);
+            if let Some(&last) = slash_indices.last() {
+                if last as usize >= unapplied.len() {
+                    return Err("slash index out of range");
+                }
+            }
+
+            // Remove from the back so that earlier indices stay valid.
+            for index in slash_indices.into_iter().rev() {
+                let slash = unapplied.remove(index as usize);
+                // Cancelled slashes no longer count against the cap of their era.
+                <EraSlashed<T>>::mutate(&slash.who, |(era, slashed)| {
+                    if *era == slash.era {
+                        *slashed = slashed.saturating_sub(slash.amount);
+                    }
+                });
+                Self::deposit_event(RawEvent::SlashCancelled(slash.who, slash.amount));
+            }
+
+            if unapplied.is_empty() {
+                <UnappliedSlashes<T>>::remove(&apply_at);
+            } else {
+                <UnappliedSlashes<T>>::insert(&apply_at, unapplied);
+            }
+            Ok(())
+        }
+
+        /// unbind amount, it'll be released after `LockedDuration`.
         pub fn unbind(origin, amount: BalanceOf<T>) -> Result{
+            let controller = ensure_signed(origin)?;
+            Self::oracle_unbind(&controller, amount)
+        }
+
+        /// bond back amount being unbonded, most recent unbonds first.
+        pub fn rebond(origin, amount: BalanceOf<T>) -> Result{
+            let controller = ensure_signed(origin)?;
+            let mut ledger = Self::oracle_ledger(&controller).ok_or("not a controller")?;
+            if ledger.unbonds.is_empty() {
+                return Err("no unbonding amount");
+            }
+
+            let mut remaining = amount;
+            while !remaining.is_zero() {
+                let last = match ledger.unbonds.last_mut() {
+                    Some(last) => last,
+                    None => break,
+                };
+                if last.amount <= remaining {
+                    remaining -= last.amount;
+                    ledger.active += last.amount;
+                    ledger.unbonds.pop();
+                } else {
+                    last.amount -= remaining;
+                    ledger.active += remaining;
+                    remaining = Zero::zero();
+                }
+            }
+
+            let rebonded = amount - remaining;
+            Self::update_ledger(&controller, &ledger);
+            Self::deposit_event(RawEvent::OracleRebonded(ledger.stash, rebonded));
+
+            // Back to candidates, unless jailed or staking too little.
+            if !<Jailed<T>>::exists(&controller)
+                && !Self::oracles().contains(&controller)
+                && ledger.active >= Self::parameters().min_staking
+            {
+                Self::add_candidates(&controller)?;
+            }
+            Ok(())
+        }
+
+        /// release unbonded amount, and remove the ledger once empty and chilled.
+        pub fn withdraw_unbonded(origin) -> Result{
+            let controller = ensure_signed(origin)?;
+            let ledger = Self::oracle_ledger(&controller).ok_or("not a controller")?;
+            let ledger = Self::release(&controller, ledger, <system::Module<T>>::block_number());
+
+            if ledger.total().is_zero()
+                && !Self::candidates().contains(&controller)
+                && !Self::oracles().contains(&controller)
+            {
+                T::Currency::remove_lock(LockedId, &ledger.stash);
+                <Bonded<T>>::remove(&ledger.stash);
+                <Payee<T>>::remove(&ledger.stash);
+                <OracleLedger<T>>::remove(&controller);
+            }
+            Ok(())
+        }
+
+        /// leave jail after the cooldown and become a candidate again.
+        pub fn unjail(origin) -> Result{
+            let who = ensure_signed(origin)?;
+            let release_at = Self::jailed(&who).ok_or("oracle is not jailed")?;
+            if <system::Module<T>>::block_number() < release_at {
+                return Err("jail cooldown has not elapsed");
+            }
+
+            <Jailed<T>>::remove(&who);
+            T::Duties::mandatory_duties().into_iter().for_each(|(service, duty)| {
+                <ConsecutiveMisses<T>>::remove(&(who.clone(), service, duty));
+            });
+            Self::add_candidates(&who)?;
+            Self::deposit_event(RawEvent::OracleUnjailed(who));
+            Ok(())
+        }
+
+        /// set the oracle key that reports on behalf of the sender's controller,
+        /// with the key's signature of `oracle_key_payload(controller)`.
+        pub fn set_oracle_key(origin, key: T::AccountId, proof: T::KeySignature) -> Result{
             let who = ensure_signed(origin)?;
-            Self::oracle_unbind(&who, amount)
+            if !<OracleLedger<T>>::exists(&who) {
+                return Err("not a controller");
+            }
+            if Self::key_owner(&key).map_or(false, |owner| owner != who) {
+                return Err("key is used by another oracle");
+            }
+            // A staked account can't be a key, or it could report for another oracle.
+            if <OracleLedger<T>>::exists(&key) || <Bonded<T>>::exists(&key) {
+                return Err("key is a staking account");
+            }
+            // Otherwise anyone could claim the key of another oracle.
+            if !proof.verify(&oracle_key_payload(&who)[..], &key) {
+                return Err("bad oracle key proof");
+            }
+
+            if let Some(old) = Self::oracle_keys(&who) {
+                <KeyOwner<T>>::remove(&old);
+            }
+            <OracleKeys<T>>::insert(&who, &key);
+            <KeyOwner<T>>::insert(&key, &who);
+            Self::deposit_event(RawEvent::OracleKeySet(who, key));
+            Ok(())
+        }
+
+        /// pay the rewards earned by an oracle stash in a past era, by anyone.
+        pub fn payout_era(origin, stash: T::AccountId, era: EraIndex) -> Result{
+            let _ = ensure_signed(origin)?;
+            let current_era = Self::current_era_index();
+            if era >= current_era {
+                return Err("era is not finished");
+            }
+            if era.saturating_add(T::HistoryDepth::get()) < current_era {
+                return Err("era is out of history");
+            }
+
+            let key = (era, stash.clone());
+            let amount = Self::eras_rewards(&key);
+            if amount.is_zero() {
+                return Err("no reward to claim");
+            }
+
+            Self::make_payout(&stash, amount)?;
+            <ErasRewards<T>>::remove(&key);
+            if let Some(controller) = Self::bonded(&stash) {
+                <OraclePerformance<T>>::mutate(&controller, |p| p.paid = p.paid.saturating_add(amount));
+            }
+            Self::deposit_event(RawEvent::RewardClaimed(stash, era, amount));
+            Ok(())
+        }
+
+        /// end the current era with an election at the end of this block.
+        pub fn force_new_era(origin) -> Result{
+            ensure_root(origin)?;
+            Self::schedule_election(<system::Module<T>>::block_number());
+            Ok(())
         }
 
         /// Actions when finalizing a block:
         ///     1. Slash/reward oracles at end of eacch block.
-        ///     2. Start an election at the right moment.
-        ///     3. Release dued locked stake.
+        ///     2. Apply deferred slashes that are due.
+        ///     3. Snapshot candidates `SnapshotLead` blocks before the election.
+        ///     4. Start an election once `NextElectionAt` is reached.
+        ///     5. Release dued locked stake.
         fn on_finalize() {
             let block_number = <system::Module<T>>::block_number();
             Self::slash_and_reward_oracles(block_number);
+            Self::apply_unapplied_slashes(block_number);
 
-            let current_era = Self::current_era();
-            if block_number == current_era + T::ElectionEra::get(){
+            let next_election_at = Self::next_election_at();
+            if block_number + T::SnapshotLead::get() >= next_election_at
+                && !<ElectionSnapshot<T>>::exists()
+            {
+                Self::snapshot_candidates();
+            }
+            // Elect even if the scheduled height was skipped.
+            if block_number >= next_election_at {
                 Self::elect_oracles();
-                <CurrentEra<T>>::put(block_number+T::ElectionEra::get());
+                <CurrentEra<T>>::put(block_number);
+                Self::new_era();
+                Self::deposit_event(RawEvent::OraclesElected(Self::current_era_index(), Self::oracles()));
+                Self::schedule_election(block_number + Self::parameters().election_era);
             }
             Self::release_locked();
         }
@@ -174,107 +910,377 @@ impl<T: Trait> Module<T> {
         current_oracles
             .iter()
             .chain(new_candidates.iter())
-            .for_each(|who| {
-                let mut ledger = Self::oracle_ledger(who);
-                let mut released = false;
+            .for_each(|controller| {
+                if let Some(ledger) = Self::oracle_ledger(controller) {
+                    Self::release(controller, ledger, current_height);
+                }
+            });
+    }
+
+    /// Release unbonds that are due, returning the updated ledger.
+    fn release(
+        controller: &T::AccountId,
+        mut ledger: Ledger<T::AccountId, BalanceOf<T>, T::BlockNumber>,
+        current_height: T::BlockNumber,
+    ) -> Ledger<T::AccountId, BalanceOf<T>, T::BlockNumber> {
+        let total = ledger.total();
+        ledger.unbonds.retain(|x| x.era > current_height);
+        let new_total = ledger.total();
+
+        if new_total < total {
+            Self::update_ledger(controller, &ledger);
+            Self::deposit_event(RawEvent::OracleStakeReleased(
+                ledger.stash.clone(),
+                total - new_total,
+            ));
+        }
+        ledger
+    }
 
-                let mut total = ledger.active;
-                for unbond in &ledger.unbonds {
-                    total += unbond.amount;
+    /// Store the ledger of a controller, locking its bonded amount in the stash.
+    fn update_ledger(
+        controller: &T::AccountId,
+        ledger: &Ledger<T::AccountId, BalanceOf<T>, T::BlockNumber>,
+    ) {
+        T::Currency::set_lock(
+            LockedId,
+            &ledger.stash,
+            ledger.total(),
+            T::BlockNumber::max_value(),
+            WithdrawReasons::all(),
+        );
+        <OracleLedger<T>>::insert(controller, ledger);
+    }
+
+    fn slash_and_reward_oracles(block_number: T::BlockNumber) {
+        let current_oracles = Self::oracles();
+        let duties = T::Duties::mandatory_duties();
+        let report_interval = Self::parameters().report_interval;
+
+        duties.iter().for_each(|&(service, duty)| {
+            let info = match Self::service(service) {
+                Some(info) => info,
+                None => return,
+            };
+
+            let mut on_time = Vec::new();
+            current_oracles.iter().for_each(|o| {
+                // Oracle might have been jailed for one of its previous duties.
+                if <Jailed<T>>::exists(o) {
+                    return;
                 }
 
-                ledger.unbonds = ledger
-                    .unbonds
-                    .into_iter()
-                    .filter(|x| {
-                        if x.era > current_height {
-                            released = true;
-                            false
-                        } else {
-                            true
-                        }
-                    })
-                    .collect();
-
-                if released {
-                    let mut still_unbonding = <BalanceOf<T>>::default();
-                    for unbond in &ledger.unbonds {
-                        still_unbonding += unbond.amount;
-                    }
-                    let new_total = ledger.active + still_unbonding;
-
-                    T::Currency::set_lock(
-                        LockedId,
-                        who,
-                        new_total,
-                        T::BlockNumber::max_value(),
-                        WithdrawReasons::all(),
-                    );
-                    <OracleLedger<T>>::insert(who, ledger);
-                    Self::deposit_event(RawEvent::OracleStakeReleased(
-                        who.clone(),
-                        total - new_total,
-                    ));
+                let key = (o.clone(), service, duty);
+                let last_report_height = Self::witness_report(&key);
+                if block_number > last_report_height + report_interval {
+                    Self::miss_report(o, service, duty, &info, block_number);
+                } else if block_number > Self::oracle_last_rewarded(&key) + report_interval {
+                    on_time.push(o.clone());
                 }
             });
+
+            if !on_time.is_empty() {
+                Self::reward(on_time, service, duty, &info, block_number);
+            }
+        });
     }
 
-    fn slash_and_reward_oracles(block_number: T::BlockNumber) {
-        let current_oracles = Self::oracles();
+    /// Reward oracles that reported a duty on time, splitting their pooled fees by accuracy.
+    fn reward(
+        oracles: Vec<T::AccountId>,
+        service: ServiceId,
+        duty: DutyId,
+        info: &ServiceInfo<BalanceOf<T>>,
+        block_number: T::BlockNumber,
+    ) {
+        let accuracies: Vec<(T::AccountId, Perbill)> = oracles
+            .into_iter()
+            .map(|o| {
+                let key = (o.clone(), service, duty);
+                <OracleLastRewarded<T>>::insert(&key, block_number);
+                Self::note_window(&o, true);
+                let accuracy = Self::accuracy(&key).unwrap_or_else(|| Perbill::from_percent(100));
+                (o, accuracy)
+            })
+            .collect();
+
+        let current_era = Self::current_era();
+        let (paid_era, paid) = Self::service_paid(service);
+        let mut paid = if paid_era == current_era {
+            paid
+        } else {
+            Zero::zero()
+        };
+        let budget = rstd::cmp::min(
+            info.fee.saturating_mul(BalanceOf::<T>::from(accuracies.len() as u32)),
+            info.reward_budget.saturating_sub(paid),
+        );
+        if budget.is_zero() {
+            return;
+        }
 
-        current_oracles.iter().for_each(|o| {
-            let last_report_height = Self::witness_report(o);
-            if block_number > last_report_height + T::ReportInteval::get() {
-                Self::slash(o, T::MissReportSlash::get());
-            } else if block_number > Self::oracle_last_rewarded(o) + T::ReportInteval::get() {
-                T::Currency::deposit_into_existing(o, T::OracleFee::get());
-                <OracleLastRewarded<T>>::insert(o, block_number.clone());
-                Self::deposit_event(RawEvent::OraclePaid(o.clone(), T::OracleFee::get()));
+        // Rewards are only recorded here, and paid when claimed after the era.
+        let era = Self::current_era_index();
+        T::RewardPolicy::distribute(budget, &accuracies)
+            .into_iter()
+            .for_each(|(who, amount)| {
+                if amount.is_zero() {
+                    return;
+                }
+                let stash = match Self::oracle_ledger(&who) {
+                    Some(ledger) => ledger.stash,
+                    None => return,
+                };
+
+                let key = (era, stash.clone());
+                if !<ErasRewards<T>>::exists(&key) {
+                    <ErasRewarded<T>>::mutate(era, |stashes| stashes.push(stash));
+                }
+                <ErasRewards<T>>::mutate(&key, |reward| *reward = reward.saturating_add(amount));
+                <ErasTotalRewards<T>>::mutate(era, |total| *total = total.saturating_add(amount));
+                paid = paid.saturating_add(amount);
+                Self::deposit_event(RawEvent::RewardEarned(who, service, duty, amount));
+            });
+        <ServicePaid<T>>::insert(service, (current_era, paid));
+    }
+
+    /// Pay a reward of a stash to its reward destination.
+    fn make_payout(stash: &T::AccountId, amount: BalanceOf<T>) -> Result {
+        match Self::payee(stash) {
+            RewardDestination::Stash => {
+                T::Currency::deposit_into_existing(stash, amount)?;
+            }
+            RewardDestination::Staked => {
+                let controller = Self::bonded(stash).ok_or("not a stash")?;
+                let mut ledger = Self::oracle_ledger(&controller).ok_or("not a controller")?;
+                T::Currency::deposit_into_existing(stash, amount)?;
+                ledger.active = ledger.active.saturating_add(amount);
+                Self::update_ledger(&controller, &ledger);
+            }
+            RewardDestination::Account(dest) => {
+                T::Currency::deposit_creating(&dest, amount);
             }
+        }
+        Ok(())
+    }
+
+    /// Oracle parameters set by governance, falling back to the module's constants.
+    pub fn parameters() -> OracleParameters<BalanceOf<T>, T::BlockNumber> {
+        Self::parameter_overrides().unwrap_or_else(|| OracleParameters {
+            min_staking: T::MinStaking::get(),
+            count: T::Count::get(),
+            report_interval: T::ReportInteval::get(),
+            election_era: T::ElectionEra::get(),
+            locked_duration: T::LockedDuration::get(),
+        })
+    }
+
+    fn schedule_election(at: T::BlockNumber) {
+        <NextElectionAt<T>>::put(at);
+        Self::deposit_event(RawEvent::ElectionScheduled(Self::current_era_index(), at));
+    }
+
+    /// Give acting oracles a full reporting window of a duty that just became mandatory.
+    fn start_duty(service: ServiceId, duty: DutyId) {
+        let current_height = <system::Module<T>>::block_number();
+        Self::oracles().into_iter().for_each(|o| {
+            let key = (o, service, duty);
+            <WitnessReport<T>>::insert(&key, current_height);
+            <OracleLastRewarded<T>>::insert(&key, current_height);
         });
     }
 
-    fn elect_oracles() {
-        let current_oracles = Self::oracles();
-        let new_candidates = Self::candidates();
-        let mut all_candidates: Vec<T::AccountId> = Vec::new();
+    /// Start a new era, pruning rewards out of history.
+    fn new_era() {
+        let ended = Self::current_era_index();
+        let current = ended.saturating_add(1);
+        CurrentEraIndex::put(current);
+        Self::deposit_event(RawEvent::EraEnded(ended, Self::eras_total_rewards(ended)));
+
+        if current > T::HistoryDepth::get() {
+            let pruned = current - T::HistoryDepth::get() - 1;
+            if <ErasTotalRewards<T>>::exists(pruned) {
+                <ErasRewarded<T>>::take(pruned).into_iter().for_each(|stash| {
+                    <ErasRewards<T>>::remove(&(pruned, stash));
+                });
+                <ErasTotalRewards<T>>::remove(pruned);
+                Self::deposit_event(RawEvent::EraPruned(pruned));
+            }
+        }
+    }
 
-        all_candidates.extend(new_candidates);
-        all_candidates.extend(current_oracles.clone());
+    fn miss_report(
+        who: &T::AccountId,
+        service: ServiceId,
+        duty: DutyId,
+        info: &ServiceInfo<BalanceOf<T>>,
+        block_number: T::BlockNumber,
+    ) {
+        let misses = match Self::record_miss(who, service, duty, block_number) {
+            Some(misses) => misses,
+            None => return,
+        };
+        if misses >= T::MaxConsecutiveMisses::get() {
+            Self::jail(who, block_number);
+        }
 
-        if all_candidates.len() == 0 {
-            return;
+        // Slashes are kept by stash, so changing the controller doesn't escape them.
+        let stash = match Self::oracle_ledger(who) {
+            Some(ledger) => ledger.stash,
+            None => return,
+        };
+        let current_era = Self::current_era();
+        let (slashed_era, slashed) = Self::era_slashed(&stash);
+        let slashed = if slashed_era == current_era {
+            slashed
+        } else {
+            Zero::zero()
+        };
+        let amount = rstd::cmp::min(
+            info.miss_report_slash.saturating_mul(misses.into()),
+            T::MaxSlashPerEra::get().saturating_sub(slashed),
+        );
+        if !amount.is_zero() {
+            <EraSlashed<T>>::insert(&stash, (current_era, slashed + amount));
+            Self::defer_slash(&stash, amount, current_era, block_number);
         }
+    }
+
+    /// Count a missed reporting window of a duty, returning the consecutive misses,
+    /// or `None` if a miss was already counted in this window.
+    fn record_miss(
+        who: &T::AccountId,
+        service: ServiceId,
+        duty: DutyId,
+        block_number: T::BlockNumber,
+    ) -> Option<u32> {
+        let key = (who.clone(), service, duty);
+        if <LastMissed<T>>::exists(&key)
+            && block_number <= Self::last_missed(&key) + Self::parameters().report_interval
+        {
+            return None;
+        }
+        <LastMissed<T>>::insert(&key, block_number);
+
+        let misses = Self::consecutive_misses(&key).saturating_add(1);
+        <ConsecutiveMisses<T>>::insert(&key, misses);
+        <OraclePerformance<T>>::mutate(who, |p| p.misses = p.misses.saturating_add(1));
+        Self::note_window(who, false);
+        Self::deposit_event(RawEvent::ReportMissed(who.clone(), service, duty, misses));
+        Some(misses)
+    }
+
+    fn jail(who: &T::AccountId, block_number: T::BlockNumber) {
+        let release_at = block_number + T::JailCooldown::get();
+        Self::remove_oracle(who);
+        // Jailed oracles stay in candidates, so their stake keeps being released,
+        // but they are skipped by elections until they unjail.
+        let _ = Self::add_candidates(who);
+        <Jailed<T>>::insert(who, release_at);
+        Self::deposit_event(RawEvent::OracleJailed(who.clone(), release_at));
+    }
 
-        if all_candidates.len() < T::Count::get().into() {
+    fn apply_unapplied_slashes(block_number: T::BlockNumber) {
+        if !<UnappliedSlashes<T>>::exists(&block_number) {
             return;
         }
 
-        let all_candidates: Vec<(&T::AccountId, Ledger<BalanceOf<T>, T::BlockNumber>)> =
-            all_candidates
-                .iter()
-                .map(|a| {
-                    let ledger = Self::oracle_ledger(a);
-                    (a, ledger)
-                })
-                .collect();
+        <UnappliedSlashes<T>>::take(&block_number)
+            .into_iter()
+            .for_each(|slash| Self::slash(&slash.who, slash.amount));
+    }
+
+    /// Rank non-jailed candidates and oracles by their weighted stake, leaving out those whose
+    /// stash balance doesn't cover their bonded amount.
+    fn snapshot_candidates() {
+        let mut snapshot: Vec<(T::AccountId, BalanceOf<T>)> = Vec::new();
+        Self::candidates()
+            .into_iter()
+            .chain(Self::oracles().into_iter())
+            .filter(|a| !<Jailed<T>>::exists(a))
+            .for_each(|a| {
+                let ledger = match Self::oracle_ledger(&a) {
+                    Some(ledger) => ledger,
+                    None => return,
+                };
+                if ledger.active.is_zero() || snapshot.iter().any(|(b, _)| b == &a) {
+                    return;
+                }
+                if T::Currency::free_balance(&ledger.stash) < ledger.total() {
+                    Self::deposit_event(RawEvent::CandidateUnbacked(a));
+                    return;
+                }
+                let weight = Self::election_weight(&a, ledger.active);
+                snapshot.push((a, weight));
+            });
+        // Most weighted candidates first.
+        snapshot.sort_by_key(|(_, weight)| rstd::cmp::Reverse(*weight));
+
+        Self::deposit_event(RawEvent::CandidatesSnapshotted(snapshot.len() as u32));
+        <ElectionSnapshot<T>>::put(snapshot);
+    }
+
+    /// Candidates of the next election and their weighted stakes, best first, once snapshotted.
+    pub fn snapshot() -> Vec<(T::AccountId, BalanceOf<T>)> {
+        Self::election_snapshot().unwrap_or_default()
+    }
+
+    /// Rank of a candidate in the election snapshot, from 0.
+    pub fn expected_rank(who: &T::AccountId) -> Option<u32> {
+        Self::snapshot()
+            .iter()
+            .position(|(a, _)| a == who)
+            .map(|rank| rank as u32)
+    }
 
-        let mut all_candidates: Vec<(&T::AccountId, Ledger<BalanceOf<T>, T::BlockNumber>)> =
-            all_candidates
-                .into_iter()
-                .filter(|(_, ledger)| ledger.active > Zero::zero())
-                .collect();
-        all_candidates.sort_by_key(|(_, ledger)| ledger.active);
+    /// Whether a controller still has an active stake, covered by the balance of its stash.
+    fn is_backed(who: &T::AccountId) -> bool {
+        Self::oracle_ledger(who).map_or(false, |ledger| {
+            !ledger.active.is_zero() && T::Currency::free_balance(&ledger.stash) >= ledger.total()
+        })
+    }
 
-        let all_candidates = all_candidates
+    /// Elect oracles from the snapshot, taken now if it's missing.
+    fn elect_oracles() {
+        if !<ElectionSnapshot<T>>::exists() {
+            Self::snapshot_candidates();
+        }
+        let snapshot = <ElectionSnapshot<T>>::take().unwrap_or_default();
+
+        let current_oracles = Self::oracles();
+        let mut all_candidates = Self::candidates();
+        all_candidates.extend(current_oracles.clone());
+
+        // Candidates that left, were jailed or are no longer backed since the snapshot are out.
+        let ranked: Vec<T::AccountId> = snapshot
             .into_iter()
-            .map(|(a, _)| a.clone())
-            .collect::<Vec<T::AccountId>>();
-        let (chosen_candidates, new_candidates) = all_candidates.split_at(T::Count::get().into());
+            .map(|(a, _)| a)
+            .filter(|a| {
+                all_candidates.contains(a) && !<Jailed<T>>::exists(a) && Self::is_backed(a)
+            })
+            .collect();
+
+        let count: usize = Self::parameters().count.into();
+        if ranked.len() == 0 || ranked.len() < count {
+            return;
+        }
 
-        let mut chosen_candidates = chosen_candidates.to_vec();
+        let mut chosen_candidates = ranked[..count].to_vec();
         chosen_candidates.sort();
 
+        // The others stay candidates, including jailed ones and those that bid after the snapshot.
+        let mut new_candidates: Vec<T::AccountId> = Vec::new();
+        all_candidates.into_iter().for_each(|a| {
+            if chosen_candidates.contains(&a) || new_candidates.contains(&a) {
+                return;
+            }
+            if <Jailed<T>>::exists(&a)
+                || Self::oracle_ledger(&a).map_or(false, |ledger| !ledger.active.is_zero())
+            {
+                new_candidates.push(a);
+            }
+        });
+
         let new_oracles: Vec<T::AccountId> = chosen_candidates
             .clone()
             .into_iter()
@@ -282,90 +1288,141 @@ impl<T: Trait> Module<T> {
             .collect();
         let outgoing_oracles: Vec<T::AccountId> = current_oracles
             .into_iter()
-            .filter(|o| !new_oracles.contains(&o))
+            .filter(|o| !chosen_candidates.contains(&o))
             .collect();
 
         let current_height = <system::Module<T>>::block_number();
+        let duties = T::Duties::mandatory_duties();
+        // New oracles get a full reporting window, neither missed nor paid before they report.
         new_oracles.iter().for_each(|o| {
-            <WitnessReport<T>>::insert(o, current_height);
+            duties.iter().for_each(|&(service, duty)| {
+                let key = (o.clone(), service, duty);
+                <WitnessReport<T>>::insert(&key, current_height);
+                <OracleLastRewarded<T>>::insert(&key, current_height);
+            });
         });
         <Oracles<T>>::put(&chosen_candidates);
         T::ChangeMembers::change_members(&new_oracles, &outgoing_oracles, chosen_candidates);
-        <OracleCandidates<T>>::put(new_candidates.to_vec());
+        <OracleCandidates<T>>::put(new_candidates);
     }
 }
 
 impl<T: Trait> Module<T> {
-    fn slash(who: &T::AccountId, amount: BalanceOf<T>) -> Result {
-        let mut ledger = Self::oracle_ledger(who);
-
-        let slash_amount = if amount > ledger.active {
-            // Remove this oracle
-            let mut current_oracles = Self::oracles();
-            current_oracles.remove_item(&who);
-            <Oracles<T>>::put(&current_oracles);
-            T::ChangeMembers::change_members(&[], &[who.clone()], current_oracles);
-            ledger.active
-        } else {
-            amount
+    /// Slash a stash out of its bonded amount, including amounts being unbonded,
+    /// or out of its free balance once it's unbonded.
+    fn slash(stash: &T::AccountId, amount: BalanceOf<T>) {
+        let controller = match Self::bonded(stash) {
+            Some(controller) => controller,
+            None => {
+                // TODO: Handle imbalance
+                let (_, missing) = T::Currency::slash(stash, amount);
+                Self::deposit_event(RawEvent::OracleSlashed(stash.clone(), amount - missing));
+                return;
+            }
+        };
+        let mut ledger = match Self::oracle_ledger(&controller) {
+            Some(ledger) => ledger,
+            None => return,
         };
 
+        if amount > ledger.active {
+            Self::remove_oracle(&controller);
+        }
+
+        // Active amount first, then amounts being unbonded, most recent first.
+        let mut remaining = amount;
+        let from_active = rstd::cmp::min(remaining, ledger.active);
+        ledger.active -= from_active;
+        remaining -= from_active;
+        for unbond in ledger.unbonds.iter_mut().rev() {
+            let from_unbond = rstd::cmp::min(remaining, unbond.amount);
+            unbond.amount -= from_unbond;
+            remaining -= from_unbond;
+        }
+        ledger.unbonds.retain(|x| !x.amount.is_zero());
+        let slash_amount = amount - remaining;
+
         // TODO: Handle imbalance
-        T::Currency::slash(who, amount);
-        ledger.active = ledger
-            .active
-            .checked_sub(&slash_amount)
-            .ok_or("Error calculating new staking")?;
-        <OracleLedger<T>>::insert(who, ledger);
+        T::Currency::slash(&ledger.stash, slash_amount);
+        Self::update_ledger(&controller, &ledger);
+        <OraclePerformance<T>>::mutate(&controller, |p| p.slashed = p.slashed.saturating_add(slash_amount));
+        Self::note_slash(&controller);
 
-        Self::deposit_event(RawEvent::OracleSlashed(who.clone(), slash_amount));
-        Ok(())
+        Self::deposit_event(RawEvent::OracleSlashed(ledger.stash, slash_amount));
+    }
+
+    /// Record whether a reporting window was reported on time, decaying older samples.
+    fn note_window(who: &T::AccountId, on_time: bool) {
+        let weight = T::ReputationDecay::get();
+        let sample = if on_time { Perbill::from_percent(100) } else { Perbill::from_parts(0) };
+        <Reputations<T>>::mutate(who, |r| {
+            r.on_time = Perbill::from_parts(decay(r.on_time.deconstruct(), sample.deconstruct(), weight));
+            r.slashes = decay(r.slashes, 0, weight);
+        });
+    }
+
+    fn note_slash(who: &T::AccountId) {
+        <Reputations<T>>::mutate(who, |r| r.slashes = r.slashes.saturating_add(SLASH_UNIT));
+    }
+
+    /// Stake weighted by reputation, as `ReputationWeight` of the stake scales with the score.
+    fn election_weight(who: &T::AccountId, stake: BalanceOf<T>) -> BalanceOf<T> {
+        let weight = T::ReputationWeight::get();
+        let unscored = Perbill::from_parts(1_000_000_000 - Self::reputation(who).score().deconstruct());
+        stake.saturating_sub(unscored * (weight * stake))
+    }
+
+    fn remove_oracle(who: &T::AccountId) {
+        let mut current_oracles = Self::oracles();
+        if current_oracles.remove_item(who).is_some() {
+            <Oracles<T>>::put(&current_oracles);
+            T::ChangeMembers::change_members(&[], &[who.clone()], current_oracles);
+        }
+    }
+
+    fn defer_slash(
+        stash: &T::AccountId,
+        amount: BalanceOf<T>,
+        era: T::BlockNumber,
+        block_number: T::BlockNumber,
+    ) {
+        let apply_at = block_number + T::SlashDeferDuration::get();
+        <UnappliedSlashes<T>>::mutate(&apply_at, |slashes| {
+            slashes.push(UnappliedSlash {
+                who: stash.clone(),
+                amount: amount,
+                era: era,
+            })
+        });
+        Self::deposit_event(RawEvent::SlashDeferred(stash.clone(), amount, apply_at));
     }
 
     fn oracle_unbind(who: &T::AccountId, amount: BalanceOf<T>) -> Result {
         let current_height = <system::Module<T>>::block_number();
-        let mut ledger = Self::oracle_ledger(who);
+        let mut ledger = Self::oracle_ledger(who).ok_or("not a controller")?;
 
         if amount > ledger.active {
             return Err("staking amount is smaller than unbonding amount");
         }
 
-        let new_unbond = Unbind {
-            amount: amount,
-            era: current_height + T::LockedDuration::get(),
-        };
+        let era = current_height + Self::parameters().locked_duration;
+        ledger.merge_unbonds();
+        let chunk = ledger.unbonds.iter().position(|x| x.era == era);
+        if chunk.is_none() && ledger.unbonds.len() >= T::MaxUnlockingChunks::get() as usize {
+            return Err("too many unlocking chunks");
+        }
 
         ledger.active = ledger
             .active
             .checked_sub(&amount)
             .ok_or("Error calculating new staking")?;
-        ledger.unbonds.push(new_unbond);
-
-        <OracleLedger<T>>::insert(who, ledger);
-        Self::deposit_event(RawEvent::OracleUnbonded(who.clone(), amount));
-        Ok(())
-    }
-
-    fn bind(who: &T::AccountId, amount: BalanceOf<T>) -> Result {
-        let mut ledger = Self::oracle_ledger(who);
-        let new_staked = ledger
-            .active
-            .checked_add(&amount)
-            .ok_or("Error calculating new staking")?;
-        if new_staked < T::MinStaking::get() {
-            return Err("staking amount is too small");
+        match chunk {
+            Some(index) => ledger.unbonds[index].amount += amount,
+            None => ledger.unbonds.push(Unbind { amount: amount, era: era }),
         }
 
-        ledger.active = new_staked;
-        <OracleLedger<T>>::insert(who, ledger);
-        T::Currency::set_lock(
-            LockedId,
-            &who,
-            amount,
-            T::BlockNumber::max_value(),
-            WithdrawReasons::all(),
-        );
-        Self::deposit_event(RawEvent::OracleBonded(who.clone(), amount));
+        <OracleLedger<T>>::insert(who, &ledger);
+        Self::deposit_event(RawEvent::OracleUnbonded(ledger.stash, amount));
         Ok(())
     }
 
@@ -378,17 +1435,105 @@ impl<T: Trait> Module<T> {
         }
         Ok(())
     }
+
+    fn remove_candidate(who: &T::AccountId) {
+        let mut candidates = Self::candidates();
+        if candidates.remove_item(who).is_some() {
+            <OracleCandidates<T>>::put(candidates);
+            Self::deposit_event(RawEvent::CandidatesRemoved(who.clone()));
+        }
+    }
 }
 
 impl<T: Trait> OracleMixedIn<T> for Module<T> {
-    fn on_witnessed(who: &T::AccountId) {
+    fn on_witnessed(who: &T::AccountId, service: ServiceId, duty: DutyId) {
         let current_height = <system::Module<T>>::block_number();
-        <WitnessReport<T>>::insert(who, current_height);
+        let key = (who.clone(), service, duty);
+        <WitnessReport<T>>::insert(&key, current_height);
+        <ConsecutiveMisses<T>>::remove(&key);
+        <OraclePerformance<T>>::mutate(who, |p| p.reports = p.reports.saturating_add(1));
     }
 
-    fn is_valid(who: &T::AccountId) -> bool {
-        let report_height = Self::witness_report(who);
-        report_height + T::ReportInteval::get() >= <system::Module<T>>::block_number()
+    fn on_duty_added(service: ServiceId, duty: DutyId) {
+        Self::start_duty(service, duty);
+    }
+
+    fn is_valid(who: &T::AccountId, service: ServiceId, duty: DutyId) -> bool {
+        let report_height = Self::witness_report(&(who.clone(), service, duty));
+        report_height + Self::parameters().report_interval >= <system::Module<T>>::block_number()
+    }
+
+    fn on_missed(who: &T::AccountId, service: ServiceId, duty: DutyId) {
+        if <Jailed<T>>::exists(who) {
+            return;
+        }
+
+        // Like missed reports, at most one miss is counted per reporting window.
+        let block_number = <system::Module<T>>::block_number();
+        if let Some(misses) = Self::record_miss(who, service, duty, block_number) {
+            if misses >= T::MaxConsecutiveMisses::get() {
+                Self::jail(who, block_number);
+            }
+        }
+    }
+
+    fn on_deviation(who: &T::AccountId, _service: ServiceId, _duty: DutyId, deviation: u32) {
+        let weight = T::ReputationDecay::get();
+        <Reputations<T>>::mutate(who, |r| r.deviation = decay(r.deviation, deviation, weight));
+    }
+
+    fn on_accuracy(who: &T::AccountId, service: ServiceId, duty: DutyId, accuracy: Perbill) {
+        <Accuracy<T>>::insert(&(who.clone(), service, duty), accuracy);
+    }
+
+    fn oracles() -> Vec<T::AccountId> {
+        <Oracles<T>>::get()
+    }
+
+    fn key_owner(key: &T::AccountId) -> Option<T::AccountId> {
+        <KeyOwner<T>>::get(key)
+    }
+
+    fn oracle_key(who: &T::AccountId) -> T::AccountId {
+        <OracleKeys<T>>::get(who).unwrap_or_else(|| who.clone())
+    }
+}
+
+/// Ensure the origin is `O`, or a signed origin of an oracle key acting for an oracle.
+/// Succeeds with the oracle account.
+pub struct EnsureOracle<T, O>(rstd::marker::PhantomData<(T, O)>);
+
+impl<T, O> EnsureOrigin<T::Origin> for EnsureOracle<T, O>
+where
+    T: Trait,
+    O: EnsureOrigin<T::Origin, Success = T::AccountId>,
+{
+    type Success = T::AccountId;
+
+    fn try_origin(o: T::Origin) -> rstd::result::Result<Self::Success, T::Origin> {
+        O::try_origin(o).or_else(|o| {
+            o.into().and_then(|o| match o {
+                system::RawOrigin::Signed(key) => match <KeyOwner<T>>::get(&key) {
+                    Some(owner) if <Oracles<T>>::get().contains(&owner) => Ok(owner),
+                    _ => Err(system::RawOrigin::Signed(key).into()),
+                },
+                r => Err(T::Origin::from(r)),
+            })
+        })
+    }
+}
+
+client::decl_runtime_apis! {
+    /// The API to query oracles from outside the runtime.
+    pub trait OracleApi<AccountId, Balance> where AccountId: codec::Codec, Balance: codec::Codec {
+        /// Reputation of an oracle.
+        fn reputation(who: AccountId) -> Reputation;
+        /// Reputation score of an oracle, as used in elections.
+        fn reputation_score(who: AccountId) -> Perbill;
+        /// Candidates of the next election and their weighted stakes, best first, once snapshotted.
+        fn election_snapshot() -> Vec<(AccountId, Balance)>;
+        /// Rank of a candidate in the election snapshot, from 0.
+        fn expected_rank(who: AccountId) -> Option<u32>;
     }
 }
 
@@ -397,22 +1542,66 @@ decl_event!(
     where
         AccountId = <T as system::Trait>::AccountId,
         Balance = BalanceOf<T>,
+        BlockNumber = <T as system::Trait>::BlockNumber,
+        Destination = RewardDestination<<T as system::Trait>::AccountId>,
+        Parameters = OracleParameters<BalanceOf<T>, <T as system::Trait>::BlockNumber>,
     {
-        /// Amount bonded by one oracle.
+        /// Amount bonded by one oracle stash.
         OracleBonded(AccountId, Balance),
-        /// Amount unbonded by one oracle.
+        /// Amount unbonded by one oracle stash.
         OracleUnbonded(AccountId, Balance),
-        /// Amount slashed to one oracle.
+        /// Amount being unbonded bonded back by one oracle stash.
+        OracleRebonded(AccountId, Balance),
+        /// Amount slashed to one oracle stash.
         OracleSlashed(AccountId, Balance),
-        /// Amount paid to one oracle.
-        OraclePaid(AccountId, Balance),
+        /// Controller set for one stash.
+        ControllerSet(AccountId, AccountId),
+        /// Reward destination set for one stash.
+        PayeeSet(AccountId, Destination),
+        /// Oracle chilled by its controller.
+        OracleChilled(AccountId),
+        /// Amount earned by one oracle for a service's duty, claimable after the era.
+        RewardEarned(AccountId, ServiceId, DutyId, Balance),
+        /// Rewards of one oracle stash for an era claimed.
+        RewardClaimed(AccountId, EraIndex, Balance),
+        /// Era ended, with total rewards earned in it.
+        EraEnded(EraIndex, Balance),
+        /// Unclaimed rewards of an era pruned out of history.
+        EraPruned(EraIndex),
+        /// Election ending an era scheduled at the given height.
+        ElectionScheduled(EraIndex, BlockNumber),
+        /// Election completed and an era started, with its acting oracles.
+        OraclesElected(EraIndex, Vec<AccountId>),
+        /// Oracle missed the reporting window of a service's duty, with its consecutive misses.
+        ReportMissed(AccountId, ServiceId, DutyId, u32),
+        /// Slash recorded for one oracle stash, to be applied at the given height.
+        SlashDeferred(AccountId, Balance, BlockNumber),
+        /// Pending slash cancelled for one oracle stash.
+        SlashCancelled(AccountId, Balance),
+        /// Oracle jailed until the given height.
+        OracleJailed(AccountId, BlockNumber),
+        /// Oracle left jail.
+        OracleUnjailed(AccountId),
+        /// Oracle key set for one oracle.
+        OracleKeySet(AccountId, AccountId),
+
+        /// Service registered or updated.
+        ServiceRegistered(ServiceId),
+        /// Service deregistered.
+        ServiceDeregistered(ServiceId),
+        /// Oracle parameters set by governance.
+        ParametersSet(Parameters),
 
         /// Candidate added.
         CandidatesAdded(AccountId),
         /// Candidate remove.
         CandidatesRemoved(AccountId),
+        /// Candidates of the next election snapshotted, with their count.
+        CandidatesSnapshotted(u32),
+        /// Candidate left out of the snapshot, as its stash balance doesn't cover its bonded amount.
+        CandidateUnbacked(AccountId),
 
-        /// Amount unlocked for one oracle.
+        /// Amount unlocked for one oracle stash.
         OracleStakeReleased(AccountId, Balance),
     }
 );
@@ -0,0 +1,111 @@
+//! Storage migrations of the oracle module, run once in `on_initialize` after a runtime upgrade.
+
+use codec::{Decode, Encode};
+use rstd::prelude::*;
+use support::traits::Get;
+use support::{StorageMap, StorageValue};
+
+use crate::{
+    Bonded, CurrentEra, Ledger, Module, NextElectionAt, OracleLastRewarded, Services, StorageVersion, Trait,
+    Unbind, WitnessReport,
+};
+
+/// Version of the current storage layout.
+pub const STORAGE_VERSION: u32 = 2;
+
+/// Ledger of version 0, kept by the oracle account itself.
+#[derive(PartialEq, Eq, Clone, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct LedgerV0<Balance, BlockNumber> {
+    pub active: Balance,
+    pub unbonds: Vec<Unbind<Balance, BlockNumber>>,
+}
+
+/// Storage items of version 0 that have changed since.
+pub mod v0 {
+    use super::LedgerV0;
+    use crate::{BalanceOf, Trait};
+    use rstd::marker::PhantomData;
+    use support::decl_storage;
+
+    pub struct Module<T: Trait>(PhantomData<T>);
+
+    decl_storage! {
+        trait Store for Module<T: Trait> as OracleStorage {
+            pub OracleLedger: map T::AccountId => Option<LedgerV0<BalanceOf<T>, T::BlockNumber>>;
+            pub WitnessReport: map T::AccountId => Option<T::BlockNumber>;
+            pub OracleLastRewarded: map T::AccountId => Option<T::BlockNumber>;
+        }
+    }
+}
+
+/// Migrate the storage from its stored version to `STORAGE_VERSION`.
+pub fn migrate<T: Trait>() {
+    let version = <Module<T>>::storage_version();
+    if version < 1 {
+        migrate_to_v1::<T>();
+    }
+    if version < 2 {
+        migrate_to_v2::<T>();
+    }
+    <StorageVersion>::put(STORAGE_VERSION);
+}
+
+/// Ledgers of version 0 are keyed by the oracle account, which becomes both its stash and
+/// controller. Unbonds released at the same era are merged on the way.
+///
+/// Version 0 had a single duty, whose reports and rewards were keyed by the oracle account. They
+/// become those of `LegacyDuty`, and its service is registered with `LegacyServiceInfo` unless it
+/// already is. Acting oracles without a report get a full reporting window from now.
+///
+/// Storage maps can't be iterated, so only the records of oracles and candidates are migrated.
+fn migrate_to_v1<T: Trait>() {
+    let current_height = <system::Module<T>>::block_number();
+    let (service, duty) = T::LegacyDuty::get();
+    if !<Services<T>>::exists(service) {
+        <Services<T>>::insert(service, T::LegacyServiceInfo::get());
+    }
+
+    let oracles = <Module<T>>::oracles();
+    let accounts = oracles
+        .iter()
+        .cloned()
+        .chain(<Module<T>>::candidates().into_iter());
+
+    for who in accounts {
+        let key = (who.clone(), service, duty);
+        match <v0::WitnessReport<T>>::take(&who) {
+            Some(reported) => <WitnessReport<T>>::insert(&key, reported),
+            // Like for a duty that just became mandatory, the window is neither missed nor paid.
+            None if oracles.contains(&who) => {
+                <WitnessReport<T>>::insert(&key, current_height);
+                <OracleLastRewarded<T>>::insert(&key, current_height);
+            }
+            None => (),
+        }
+        if let Some(rewarded) = <v0::OracleLastRewarded<T>>::take(&who) {
+            <OracleLastRewarded<T>>::mutate(&key, |last| *last = rstd::cmp::max(*last, rewarded));
+        }
+
+        if let Some(old) = <v0::OracleLedger<T>>::take(&who) {
+            let mut ledger = Ledger {
+                stash: who.clone(),
+                active: old.active,
+                unbonds: old.unbonds,
+            };
+            ledger.merge_unbonds();
+            <Bonded<T>>::insert(&who, &who);
+            <Module<T>>::update_ledger(&who, &ledger);
+        }
+    }
+}
+
+/// Up to version 1, `CurrentEra` was set to the height of an election plus `ElectionEra`, and the
+/// next election happened when the height was exactly `CurrentEra + ElectionEra`. That election is
+/// scheduled in `NextElectionAt`, and `CurrentEra` becomes the start of the current era, which is
+/// taken to be the height of the migration.
+fn migrate_to_v2<T: Trait>() {
+    let at = <Module<T>>::current_era() + <Module<T>>::parameters().election_era;
+    <NextElectionAt<T>>::put(at);
+    <CurrentEra<T>>::put(<system::Module<T>>::block_number());
+}
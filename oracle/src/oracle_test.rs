@@ -1,12 +1,13 @@
 /// tests for this module
 #[cfg(test)]
 mod tests {
-	use super::*;
+	use super::super::*;
+	use crate::migration::{self, LedgerV0};
 
 	use runtime_io::with_externalities;
 	use primitives::{H256, Blake2Hasher};
-	use support::{impl_outer_origin, assert_ok, parameter_types};
-	use sr_primitives::{traits::{BlakeTwo256, IdentityLookup}, testing::Header};
+	use support::{impl_outer_origin, assert_noop, assert_ok, parameter_types};
+	use sr_primitives::{traits::{BlakeTwo256, ConvertInto, IdentityLookup, Lazy, OnFinalize, OnInitialize}, testing::Header};
 	use sr_primitives::weights::Weight;
 	use sr_primitives::Perbill;
 
@@ -43,25 +44,872 @@ mod tests {
 		type AvailableBlockRatio = AvailableBlockRatio;
 		type Version = ();
 	}
+	parameter_types! {
+		pub const ExistentialDeposit: u64 = 0;
+		pub const TransferFee: u64 = 0;
+		pub const CreationFee: u64 = 0;
+		pub const TransactionBaseFee: u64 = 0;
+		pub const TransactionByteFee: u64 = 0;
+	}
+	impl balances::Trait for Test {
+		type Balance = u64;
+		type OnFreeBalanceZero = ();
+		type OnNewAccount = ();
+		type Event = ();
+		type TransactionPayment = ();
+		type DustRemoval = ();
+		type TransferPayment = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type TransferFee = TransferFee;
+		type CreationFee = CreationFee;
+		type TransactionBaseFee = TransactionBaseFee;
+		type TransactionByteFee = TransactionByteFee;
+		type WeightToFee = ConvertInto;
+	}
+	parameter_types! {
+		pub const MaxSlashPerEra: u64 = 100;
+		pub const MaxConsecutiveMisses: u32 = 3;
+		pub const JailCooldown: u64 = 10;
+		pub const MinStaking: u64 = 10;
+		pub const Count: u16 = 3;
+		pub const ReportInteval: u64 = 10;
+		pub const ElectionEra: u64 = 10;
+		pub const LockedDuration: u64 = 100;
+		pub const SlashDeferDuration: u64 = 10;
+		pub const ReputationDecay: Perbill = Perbill::from_percent(10);
+		pub const ReputationWeight: Perbill = Perbill::from_percent(50);
+		pub const HistoryDepth: EraIndex = 84;
+		pub const MaxUnlockingChunks: u32 = 2;
+		pub const SnapshotLead: u64 = 2;
+		pub const LegacyDuty: (ServiceId, DutyId) = (0, 0);
+		pub const LegacyServiceInfo: ServiceInfo<u64> = ServiceInfo {
+			fee: 5,
+			miss_report_slash: 7,
+			reward_budget: u64::max_value(),
+		};
+	}
+	/// Signature valid for its own signer only.
+	#[derive(Clone, Eq, PartialEq, Debug, Encode, Decode)]
+	pub struct TestSignature(u64);
+	impl Verify for TestSignature {
+		type Signer = u64;
+		fn verify<L: Lazy<[u8]>>(&self, _: L, signer: &u64) -> bool {
+			self.0 == *signer
+		}
+	}
+
+	/// One mandatory duty, of service 0.
+	pub struct TestDuties;
+	impl OracleDuties for TestDuties {
+		fn mandatory_duties() -> Vec<(ServiceId, DutyId)> {
+			vec![(0, 0)]
+		}
+	}
+	/// Account 100 stands for governance.
+	pub struct EnsureGovernance;
+	impl EnsureOrigin<Origin> for EnsureGovernance {
+		type Success = ();
+		fn try_origin(o: Origin) -> Result<(), Origin> {
+			o.into().and_then(|o| match o {
+				system::RawOrigin::Signed(100) => Ok(()),
+				r => Err(Origin::from(r)),
+			})
+		}
+	}
 	impl Trait for Test {
 		type Event = ();
+		type Currency = Balances;
+		type MaxSlashPerEra = MaxSlashPerEra;
+		type MaxConsecutiveMisses = MaxConsecutiveMisses;
+		type JailCooldown = JailCooldown;
+		type MinStaking = MinStaking;
+		type MaliciousSlashOrigin = system::EnsureRoot<u64>;
+		type ParametersOrigin = EnsureGovernance;
+		type Count = Count;
+		type ReportInteval = ReportInteval;
+		type ElectionEra = ElectionEra;
+		type LockedDuration = LockedDuration;
+		type SlashDeferDuration = SlashDeferDuration;
+		type ChangeMembers = ();
+		type Duties = TestDuties;
+		type ReputationDecay = ReputationDecay;
+		type ReputationWeight = ReputationWeight;
+		type RewardPolicy = EqualReward;
+		type HistoryDepth = HistoryDepth;
+		type MaxUnlockingChunks = MaxUnlockingChunks;
+		type SnapshotLead = SnapshotLead;
+		type KeySignature = TestSignature;
+		type LegacyDuty = LegacyDuty;
+		type LegacyServiceInfo = LegacyServiceInfo;
 	}
-	type TemplateModule = Module<Test>;
+	type Balances = balances::Module<Test>;
+	type Oracle = Module<Test>;
 
 	// This function basically just builds a genesis storage key/value store according to
 	// our desired mockup.
 	fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
-		system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+		let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		balances::GenesisConfig::<Test> {
+			balances: vec![(1, 1000), (2, 1000), (3, 1000)],
+			vesting: vec![],
+		}.assimilate_storage(&mut t).unwrap();
+		GenesisConfig::<Test> {
+			services: vec![],
+		}.assimilate_storage(&mut t).unwrap();
+		t.into()
+	}
+
+	fn unbind(amount: u64, era: u64) -> Unbind<u64, u64> {
+		Unbind { amount, era }
+	}
+
+	fn register_service(miss_report_slash: u64) {
+		assert_ok!(Oracle::register_service(Origin::ROOT, 0, ServiceInfo {
+			fee: 0,
+			miss_report_slash,
+			reward_budget: 0,
+		}));
+	}
+
+	/// Bond each stash to its controller, and make the controllers acting oracles.
+	fn set_oracles(bonds: &[(u64, u64, u64)]) {
+		for &(stash, controller, value) in bonds {
+			assert_ok!(Oracle::bond(Origin::signed(stash), controller, value));
+		}
+		<Oracles<Test>>::put(bonds.iter().map(|&(_, controller, _)| controller).collect::<Vec<_>>());
+	}
+
+	/// Make controller 10 of stash 1 earn `fee` in the current era, for reporting at block 5.
+	fn earn_reward(fee: u64) {
+		assert_ok!(Oracle::register_service(Origin::ROOT, 0, ServiceInfo {
+			fee,
+			miss_report_slash: 0,
+			reward_budget: 1000,
+		}));
+		set_oracles(&[(1, 10, 100)]);
+		system::Module::<Test>::set_block_number(5);
+		<Oracle as OracleMixedIn<Test>>::on_witnessed(&10, 0, 0);
+		Oracle::slash_and_reward_oracles(11);
+	}
+
+	#[test]
+	fn genesis_is_at_current_storage_version() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_eq!(Oracle::storage_version(), migration::STORAGE_VERSION);
+		});
+	}
+
+	#[test]
+	fn migrates_v0_ledgers_of_oracles_and_candidates() {
+		with_externalities(&mut new_test_ext(), || {
+			<StorageVersion>::put(0u32);
+			<Oracles<Test>>::put(vec![1]);
+			<OracleCandidates<Test>>::put(vec![2]);
+			<migration::v0::OracleLedger<Test>>::insert(1, LedgerV0 {
+				active: 100,
+				unbonds: vec![unbind(10, 50), unbind(20, 50), unbind(30, 60)],
+			});
+			<migration::v0::OracleLedger<Test>>::insert(2, LedgerV0 {
+				active: 200,
+				unbonds: vec![],
+			});
+
+			Oracle::on_initialize(1);
+
+			assert_eq!(Oracle::storage_version(), migration::STORAGE_VERSION);
+			assert_eq!(Oracle::oracle_ledger(1), Some(Ledger {
+				stash: 1,
+				active: 100,
+				unbonds: vec![unbind(30, 50), unbind(30, 60)],
+			}));
+			assert_eq!(Oracle::oracle_ledger(2), Some(Ledger {
+				stash: 2,
+				active: 200,
+				unbonds: vec![],
+			}));
+			assert_eq!(Oracle::bonded(1), Some(1));
+			assert_eq!(Oracle::bonded(2), Some(2));
+			assert_eq!(Balances::locks(1)[0].amount, 160);
+			assert_eq!(Oracle::next_election_at(), 10);
+		});
+	}
+
+	#[test]
+	fn migrates_v0_duty_records_to_legacy_service() {
+		with_externalities(&mut new_test_ext(), || {
+			system::Module::<Test>::set_block_number(30);
+			<StorageVersion>::put(0u32);
+			<Oracles<Test>>::put(vec![1, 2]);
+			<OracleCandidates<Test>>::put(vec![3]);
+			<migration::v0::WitnessReport<Test>>::insert(1, 25);
+			<migration::v0::OracleLastRewarded<Test>>::insert(1, 20);
+			<migration::v0::WitnessReport<Test>>::insert(3, 15);
+
+			Oracle::on_initialize(30);
+
+			assert_eq!(Oracle::service(0), Some(LegacyServiceInfo::get()));
+			assert_eq!(Oracle::witness_report(&(1, 0, 0)), 25);
+			assert_eq!(Oracle::oracle_last_rewarded(&(1, 0, 0)), 20);
+			assert_eq!(Oracle::witness_report(&(2, 0, 0)), 30);
+			assert_eq!(Oracle::witness_report(&(3, 0, 0)), 15);
+			assert_eq!(<migration::v0::WitnessReport<Test>>::get(1), None);
+			assert_eq!(<migration::v0::OracleLastRewarded<Test>>::get(1), None);
+
+			assert_eq!(Oracle::oracle_last_rewarded(&(2, 0, 0)), 30);
+
+			// Oracle 2 got a full window, oracle 1 keeps the one of its last report.
+			Oracle::slash_and_reward_oracles(36);
+			assert_eq!(Oracle::consecutive_misses(&(1, 0, 0)), 1);
+			assert_eq!(Oracle::consecutive_misses(&(2, 0, 0)), 0);
+			// Nor is oracle 2 paid for a window it didn't report in.
+			assert_eq!(Oracle::oracle_last_rewarded(&(2, 0, 0)), 30);
+		});
+	}
+
+	#[test]
+	fn migration_keeps_registered_legacy_service() {
+		with_externalities(&mut new_test_ext(), || {
+			register_service(30);
+			<StorageVersion>::put(0u32);
+
+			Oracle::on_initialize(1);
+
+			assert_eq!(Oracle::service(0).unwrap().miss_report_slash, 30);
+		});
+	}
+
+	#[test]
+	fn migration_runs_once() {
+		with_externalities(&mut new_test_ext(), || {
+			<StorageVersion>::put(0u32);
+			<Oracles<Test>>::put(vec![1]);
+			<migration::v0::OracleLedger<Test>>::insert(1, LedgerV0 {
+				active: 100,
+				unbonds: vec![],
+			});
+			Oracle::on_initialize(1);
+
+			let ledger = Ledger { stash: 3, active: 50, unbonds: vec![] };
+			<OracleLedger<Test>>::insert(1, &ledger);
+			Oracle::on_initialize(2);
+
+			assert_eq!(Oracle::oracle_ledger(1), Some(ledger));
+		});
+	}
+
+	#[test]
+	fn migrates_v1_election_schedule() {
+		with_externalities(&mut new_test_ext(), || {
+			system::Module::<Test>::set_block_number(25);
+			<StorageVersion>::put(1u32);
+			<CurrentEra<Test>>::put(30);
+
+			Oracle::on_initialize(25);
+
+			assert_eq!(Oracle::next_election_at(), 40);
+			assert_eq!(Oracle::current_era(), 25);
+		});
+	}
+
+	#[test]
+	fn elects_even_if_scheduled_height_is_skipped() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_eq!(Oracle::next_election_at(), 10);
+
+			system::Module::<Test>::set_block_number(12);
+			Oracle::on_finalize(12);
+
+			assert_eq!(Oracle::current_era_index(), 1);
+			assert_eq!(Oracle::current_era(), 12);
+			assert_eq!(Oracle::next_election_at(), 22);
+		});
+	}
+
+	#[test]
+	fn force_new_era_elects_at_end_of_block() {
+		with_externalities(&mut new_test_ext(), || {
+			system::Module::<Test>::set_block_number(3);
+			assert_noop!(Oracle::force_new_era(Origin::signed(1)), "bad origin: expected to be a root origin");
+			assert_ok!(Oracle::force_new_era(Origin::ROOT));
+			assert_eq!(Oracle::next_election_at(), 3);
+
+			Oracle::on_finalize(3);
+
+			assert_eq!(Oracle::current_era_index(), 1);
+			assert_eq!(Oracle::next_election_at(), 13);
+		});
+	}
+
+	#[test]
+	fn parameters_default_to_constants_until_set() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_eq!(Oracle::parameters(), OracleParameters {
+				min_staking: 10,
+				count: 3,
+				report_interval: 10,
+				election_era: 10,
+				locked_duration: 100,
+			});
+
+			let parameters = OracleParameters {
+				min_staking: 500,
+				count: 5,
+				report_interval: 20,
+				election_era: 30,
+				locked_duration: 40,
+			};
+			assert_noop!(Oracle::set_parameters(Origin::signed(1), parameters.clone()), "bad origin");
+			assert_noop!(
+				Oracle::set_parameters(Origin::ROOT, OracleParameters { count: 0, ..parameters.clone() }),
+				"oracle count is zero"
+			);
+			assert_ok!(Oracle::set_parameters(Origin::ROOT, parameters.clone()));
+			assert_eq!(Oracle::parameters(), parameters);
+
+			assert_noop!(Oracle::bond(Origin::signed(1), 10, 100), "staking amount is too small");
+
+			system::Module::<Test>::set_block_number(10);
+			Oracle::on_finalize(10);
+			assert_eq!(Oracle::next_election_at(), 40);
+		});
+	}
+
+	#[test]
+	fn services_are_adjusted_by_governance() {
+		with_externalities(&mut new_test_ext(), || {
+			let info = ServiceInfo { fee: 5, miss_report_slash: 10, reward_budget: 100 };
+			assert_noop!(Oracle::register_service(Origin::signed(1), 0, info.clone()), "bad origin");
+			assert_ok!(Oracle::register_service(Origin::signed(100), 0, info.clone()));
+			assert_ok!(Oracle::register_service(Origin::signed(100), 0, ServiceInfo { fee: 8, ..info.clone() }));
+			assert_eq!(Oracle::service(0).unwrap().fee, 8);
+
+			assert_noop!(Oracle::deregister_service(Origin::signed(1), 0), "bad origin");
+			assert_ok!(Oracle::deregister_service(Origin::signed(100), 0));
+			assert_eq!(Oracle::service(0), None);
+			assert_ok!(Oracle::register_service(Origin::ROOT, 0, info));
+			assert_ok!(Oracle::set_parameters(Origin::signed(100), Oracle::parameters()));
+		});
+	}
+
+	#[test]
+	fn elects_from_snapshot_of_backed_candidates() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Oracle::set_parameters(Origin::ROOT, OracleParameters {
+				count: 2,
+				..Oracle::parameters()
+			}));
+			for &(stash, controller, value) in &[(1, 10, 100), (2, 20, 200), (3, 30, 300)] {
+				assert_ok!(Oracle::bond(Origin::signed(stash), controller, value));
+				assert_ok!(Oracle::bid(Origin::signed(controller)));
+			}
+			// Stash 3 no longer covers its bonded amount.
+			Balances::make_free_balance_be(&3, 100);
+
+			system::Module::<Test>::set_block_number(7);
+			Oracle::on_finalize(7);
+			assert_eq!(Oracle::election_snapshot(), None);
+
+			system::Module::<Test>::set_block_number(8);
+			Oracle::on_finalize(8);
+			assert_eq!(Oracle::snapshot().len(), 2);
+			assert_eq!(Oracle::expected_rank(&20), Some(0));
+			assert_eq!(Oracle::expected_rank(&10), Some(1));
+			assert_eq!(Oracle::expected_rank(&30), None);
+
+			system::Module::<Test>::set_block_number(10);
+			Oracle::on_finalize(10);
+			assert_eq!(Oracle::oracles(), vec![10, 20]);
+			assert_eq!(Oracle::candidates(), vec![30]);
+			assert_eq!(Oracle::election_snapshot(), None);
+			// New oracles start a reporting window of each mandatory duty.
+			assert_eq!(Oracle::witness_report(&(20, 0, 0)), 10);
+			assert_eq!(Oracle::oracle_last_rewarded(&(20, 0, 0)), 10);
+		});
+	}
+
+	#[test]
+	fn elects_only_candidates_still_backed_at_election() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Oracle::set_parameters(Origin::ROOT, OracleParameters {
+				count: 1,
+				..Oracle::parameters()
+			}));
+			for &(stash, controller, value) in &[(1, 10, 100), (2, 20, 200), (3, 30, 300)] {
+				assert_ok!(Oracle::bond(Origin::signed(stash), controller, value));
+				assert_ok!(Oracle::bid(Origin::signed(controller)));
+			}
+			system::Module::<Test>::set_block_number(8);
+			Oracle::on_finalize(8);
+			assert_eq!(Oracle::expected_rank(&30), Some(0));
+			assert_eq!(Oracle::expected_rank(&20), Some(1));
+
+			// After the snapshot, 30 no longer covers its bonded amount and 20 unbinds it all.
+			Balances::make_free_balance_be(&3, 100);
+			assert_ok!(Oracle::unbind(Origin::signed(20), 200));
+
+			system::Module::<Test>::set_block_number(10);
+			Oracle::on_finalize(10);
+			assert_eq!(Oracle::oracles(), vec![10]);
+			assert_eq!(Oracle::candidates(), vec![30]);
+		});
+	}
+
+	#[test]
+	fn decay_moves_towards_sample_by_weight() {
+		assert_eq!(decay(1000, 0, Perbill::from_percent(10)), 900);
+		assert_eq!(decay(0, 1000, Perbill::from_percent(10)), 100);
+		assert_eq!(decay(500, 500, Perbill::from_percent(10)), 500);
+		assert_eq!(decay(1000, 0, Perbill::from_parts(0)), 1000);
+		assert_eq!(decay(1000, 0, Perbill::from_percent(100)), 0);
+	}
+
+	#[test]
+	fn reputation_score_is_lowered_by_deviation_and_slashes() {
+		let perfect = Reputation { on_time: Perbill::from_percent(100), deviation: 0, slashes: 0 };
+		assert_eq!(perfect.score(), Perbill::from_percent(100));
+		// New oracles start neutral.
+		assert_eq!(Reputation::default().score(), Perbill::from_percent(50));
+		assert_eq!(Reputation { deviation: 5_000, ..perfect.clone() }.score(), Perbill::from_percent(50));
+		assert_eq!(Reputation { deviation: 20_000, ..perfect.clone() }.score(), Perbill::from_parts(0));
+		assert_eq!(Reputation { slashes: SLASH_UNIT, ..perfect.clone() }.score(), Perbill::from_percent(50));
+		assert_eq!(Reputation { slashes: 3 * SLASH_UNIT, ..perfect.clone() }.score(), Perbill::from_percent(25));
+		assert_eq!(Reputation { on_time: Perbill::from_parts(0), ..perfect }.score(), Perbill::from_parts(0));
+	}
+
+	#[test]
+	fn windows_and_slashes_update_reputation() {
+		with_externalities(&mut new_test_ext(), || {
+			Oracle::note_window(&10, true);
+			assert_eq!(Oracle::reputation(10).on_time, Perbill::from_percent(55));
+			Oracle::note_window(&10, false);
+			assert_eq!(Oracle::reputation(10).on_time, Perbill::from_parts(495_000_000));
+
+			Oracle::note_slash(&10);
+			Oracle::note_slash(&10);
+			assert_eq!(Oracle::reputation(10).slashes, 2 * SLASH_UNIT);
+			// Slashes decay along with each window.
+			Oracle::note_window(&10, true);
+			assert_eq!(Oracle::reputation(10).slashes, 1_800);
+			assert_eq!(Oracle::reputation(20), Reputation::default());
+		});
+	}
+
+	#[test]
+	fn elections_rank_candidates_by_reputation_weighted_stake() {
+		with_externalities(&mut new_test_ext(), || {
+			for &(stash, controller, value) in &[(1, 10, 100), (2, 20, 200), (3, 30, 300)] {
+				assert_ok!(Oracle::bond(Origin::signed(stash), controller, value));
+				assert_ok!(Oracle::bid(Origin::signed(controller)));
+			}
+			<Reputations<Test>>::insert(20, Reputation { on_time: Perbill::from_percent(100), deviation: 0, slashes: 0 });
+			<Reputations<Test>>::insert(30, Reputation { on_time: Perbill::from_parts(0), deviation: 0, slashes: 0 });
+
+			// Half of the stake scales with the score.
+			assert_eq!(Oracle::election_weight(&10, 100), 75);
+			assert_eq!(Oracle::election_weight(&20, 200), 200);
+			assert_eq!(Oracle::election_weight(&30, 300), 150);
+
+			system::Module::<Test>::set_block_number(8);
+			Oracle::on_finalize(8);
+			assert_eq!(Oracle::snapshot(), vec![(20, 200), (30, 150), (10, 75)]);
+		});
+	}
+
+	#[test]
+	fn unbind_merges_chunks_and_bounds_them() {
+		with_externalities(&mut new_test_ext(), || {
+			system::Module::<Test>::set_block_number(1);
+			assert_ok!(Oracle::bond(Origin::signed(1), 10, 100));
+
+			assert_ok!(Oracle::unbind(Origin::signed(10), 10));
+			assert_ok!(Oracle::unbind(Origin::signed(10), 10));
+			assert_eq!(Oracle::oracle_ledger(10).unwrap().unbonds, vec![unbind(20, 101)]);
+
+			system::Module::<Test>::set_block_number(2);
+			assert_ok!(Oracle::unbind(Origin::signed(10), 10));
+			system::Module::<Test>::set_block_number(3);
+			assert_noop!(Oracle::unbind(Origin::signed(10), 10), "too many unlocking chunks");
+		});
+	}
+
+	#[test]
+	fn miss_report_slashes_are_deferred_then_applied() {
+		with_externalities(&mut new_test_ext(), || {
+			register_service(80);
+			set_oracles(&[(1, 10, 100)]);
+			system::Module::<Test>::set_block_number(1);
+			assert_ok!(Oracle::unbind(Origin::signed(10), 50));
+
+			Oracle::slash_and_reward_oracles(11);
+			assert_eq!(Oracle::unapplied_slashes(21), vec![UnappliedSlash { who: 1, amount: 80, era: 0 }]);
+			assert_eq!(Oracle::era_slashed(1), (0, 80));
+			assert_eq!(Balances::free_balance(&1), 1000);
+
+			Oracle::apply_unapplied_slashes(21);
+			assert!(!<UnappliedSlashes<Test>>::exists(21));
+			assert_eq!(Balances::free_balance(&1), 920);
+			// The active amount is slashed first, then the amount being unbonded.
+			assert_eq!(Oracle::oracle_ledger(10), Some(Ledger {
+				stash: 1,
+				active: 0,
+				unbonds: vec![unbind(20, 101)],
+			}));
+			assert_eq!(Balances::locks(1)[0].amount, 20);
+			assert_eq!(Oracle::oracles(), Vec::<u64>::new());
+		});
+	}
+
+	#[test]
+	fn cancelled_slashes_are_not_applied_nor_capped() {
+		with_externalities(&mut new_test_ext(), || {
+			register_service(80);
+			set_oracles(&[(1, 10, 100)]);
+			Oracle::slash_and_reward_oracles(11);
+
+			assert_noop!(Oracle::cancel_deferred_slash(Origin::signed(1), 21, vec![0]), "bad origin");
+			assert_noop!(Oracle::cancel_deferred_slash(Origin::ROOT, 21, vec![1]), "slash index out of range");
+			assert_ok!(Oracle::cancel_deferred_slash(Origin::ROOT, 21, vec![0]));
+			assert!(!<UnappliedSlashes<Test>>::exists(21));
+			assert_eq!(Oracle::era_slashed(1), (0, 0));
+
+			Oracle::apply_unapplied_slashes(21);
+			assert_eq!(Balances::free_balance(&1), 1000);
+			assert_eq!(Oracle::oracle_ledger(10).unwrap().active, 100);
+
+			// The cancelled amount is available again to the era's cap.
+			Oracle::slash_and_reward_oracles(22);
+			assert_eq!(Oracle::unapplied_slashes(32), vec![UnappliedSlash { who: 1, amount: 100, era: 0 }]);
+		});
+	}
+
+	#[test]
+	fn deferred_slash_follows_stash_to_new_controller() {
+		with_externalities(&mut new_test_ext(), || {
+			register_service(30);
+			set_oracles(&[(1, 10, 100)]);
+			Oracle::slash_and_reward_oracles(11);
+
+			assert_eq!(Oracle::reputation(10).on_time, Perbill::from_percent(45));
+
+			assert_ok!(Oracle::chill(Origin::signed(10)));
+			assert_ok!(Oracle::set_controller(Origin::signed(1), 11));
+			// The track record moves along with the stash.
+			assert_eq!(Oracle::reputation(11).on_time, Perbill::from_percent(45));
+			assert_eq!(Oracle::performance(11).misses, 1);
+			assert!(!<Reputations<Test>>::exists(10));
+			assert!(!<OraclePerformance<Test>>::exists(10));
+			Oracle::apply_unapplied_slashes(21);
+
+			assert_eq!(Balances::free_balance(&1), 970);
+			assert_eq!(Oracle::oracle_ledger(11).unwrap().active, 70);
+			assert_eq!(Oracle::performance(11).slashed, 30);
+			assert_eq!(Oracle::reputation(11).slashes, SLASH_UNIT);
+		});
+	}
+
+	#[test]
+	fn slash_by_vote_takes_bonded_amount_of_stash() {
+		with_externalities(&mut new_test_ext(), || {
+			set_oracles(&[(1, 10, 100)]);
+			assert_noop!(Oracle::slash_by_vote(Origin::signed(1), 10, 30), "bad origin");
+
+			assert_ok!(Oracle::slash_by_vote(Origin::ROOT, 10, 30));
+			assert_eq!(Balances::free_balance(&1), 970);
+			assert_eq!(Oracle::oracle_ledger(10).unwrap().active, 70);
+			assert_eq!(Oracle::performance(10).slashed, 30);
+			assert_eq!(Oracle::reputation(10).slashes, SLASH_UNIT);
+
+			// Slashing more than the active amount removes the oracle.
+			assert_ok!(Oracle::slash_by_vote(Origin::ROOT, 10, 100));
+			assert_eq!(Balances::free_balance(&1), 900);
+			assert_eq!(Oracle::oracle_ledger(10).unwrap().active, 0);
+			assert_eq!(Oracle::oracles(), Vec::<u64>::new());
+
+			// Accounts that aren't bonded are slashed directly.
+			assert_ok!(Oracle::slash_by_vote(Origin::ROOT, 2, 50));
+			assert_eq!(Balances::free_balance(&2), 950);
+		});
+	}
+
+	#[test]
+	fn consecutive_misses_grow_penalties_up_to_era_cap_and_jail() {
+		with_externalities(&mut new_test_ext(), || {
+			register_service(30);
+			set_oracles(&[(1, 10, 500)]);
+
+			Oracle::slash_and_reward_oracles(11);
+			Oracle::slash_and_reward_oracles(22);
+			// Only one miss is counted per reporting window.
+			Oracle::slash_and_reward_oracles(25);
+			assert_eq!(Oracle::consecutive_misses(&(10, 0, 0)), 2);
+			assert_eq!(Oracle::unapplied_slashes(21)[0].amount, 30);
+			assert_eq!(Oracle::unapplied_slashes(32)[0].amount, 60);
+			assert!(!<UnappliedSlashes<Test>>::exists(35));
+			assert_eq!(Oracle::jailed(10), None);
+
+			Oracle::slash_and_reward_oracles(33);
+			assert_eq!(Oracle::unapplied_slashes(43)[0].amount, 10);
+			assert_eq!(Oracle::era_slashed(1), (0, 100));
+			assert_eq!(Oracle::jailed(10), Some(43));
+			assert_eq!(Oracle::oracles(), Vec::<u64>::new());
+			assert_eq!(Oracle::candidates(), vec![10]);
+		});
+	}
+
+	#[test]
+	fn era_slash_cap_resets_in_new_era() {
+		with_externalities(&mut new_test_ext(), || {
+			register_service(30);
+			set_oracles(&[(1, 10, 500)]);
+			Oracle::slash_and_reward_oracles(11);
+			Oracle::slash_and_reward_oracles(22);
+
+			<CurrentEra<Test>>::put(25);
+			Oracle::slash_and_reward_oracles(33);
+
+			assert_eq!(Oracle::unapplied_slashes(43)[0].amount, 90);
+			assert_eq!(Oracle::era_slashed(1), (25, 90));
+		});
+	}
+
+	#[test]
+	fn misses_told_by_services_count_once_per_window() {
+		with_externalities(&mut new_test_ext(), || {
+			register_service(10);
+			set_oracles(&[(1, 10, 100)]);
+			system::Module::<Test>::set_block_number(5);
+			for _ in 0..5 {
+				<Oracle as OracleMixedIn<Test>>::on_missed(&10, 0, 0);
+			}
+			assert_eq!(Oracle::consecutive_misses(&(10, 0, 0)), 1);
+			assert_eq!(Oracle::jailed(10), None);
+
+			// Nor is a missed report counted again in the same window.
+			Oracle::slash_and_reward_oracles(15);
+			assert_eq!(Oracle::consecutive_misses(&(10, 0, 0)), 1);
+
+			system::Module::<Test>::set_block_number(16);
+			<Oracle as OracleMixedIn<Test>>::on_missed(&10, 0, 0);
+			<Oracle as OracleMixedIn<Test>>::on_missed(&10, 0, 0);
+			assert_eq!(Oracle::consecutive_misses(&(10, 0, 0)), 2);
+		});
+	}
+
+	#[test]
+	fn jailed_oracle_unjails_after_cooldown() {
+		with_externalities(&mut new_test_ext(), || {
+			register_service(30);
+			set_oracles(&[(1, 10, 500)]);
+			for &block in &[11, 22, 33] {
+				Oracle::slash_and_reward_oracles(block);
+			}
+			assert_eq!(Oracle::jailed(10), Some(43));
+
+			system::Module::<Test>::set_block_number(42);
+			assert_noop!(Oracle::unjail(Origin::signed(10)), "jail cooldown has not elapsed");
+			assert_noop!(Oracle::bid(Origin::signed(10)), "oracle is jailed");
+			assert_noop!(Oracle::unjail(Origin::signed(1)), "oracle is not jailed");
+
+			system::Module::<Test>::set_block_number(43);
+			assert_ok!(Oracle::unjail(Origin::signed(10)));
+			assert_eq!(Oracle::jailed(10), None);
+			assert_eq!(Oracle::consecutive_misses(&(10, 0, 0)), 0);
+			assert_eq!(Oracle::candidates(), vec![10]);
+		});
+	}
+
+	#[test]
+	fn new_service_gives_oracles_a_full_reporting_window() {
+		with_externalities(&mut new_test_ext(), || {
+			set_oracles(&[(1, 10, 100)]);
+			system::Module::<Test>::set_block_number(50);
+			register_service(30);
+			assert_eq!(Oracle::witness_report(&(10, 0, 0)), 50);
+
+			Oracle::slash_and_reward_oracles(60);
+			assert_eq!(Oracle::consecutive_misses(&(10, 0, 0)), 0);
+			Oracle::slash_and_reward_oracles(61);
+			assert_eq!(Oracle::consecutive_misses(&(10, 0, 0)), 1);
+		});
+	}
+
+	#[test]
+	fn oracle_key_is_set_by_controller_with_proof() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_noop!(Oracle::set_oracle_key(Origin::signed(10), 50, TestSignature(50)), "not a controller");
+			assert_ok!(Oracle::bond(Origin::signed(1), 10, 100));
+			assert_ok!(Oracle::bond(Origin::signed(2), 20, 100));
+
+			assert_noop!(Oracle::set_oracle_key(Origin::signed(10), 50, TestSignature(10)), "bad oracle key proof");
+			assert_noop!(Oracle::set_oracle_key(Origin::signed(10), 2, TestSignature(2)), "key is a staking account");
+			assert_ok!(Oracle::set_oracle_key(Origin::signed(10), 50, TestSignature(50)));
+			assert_eq!(Oracle::oracle_keys(10), Some(50));
+			assert_eq!(Oracle::key_owner(50), Some(10));
+
+			assert_noop!(
+				Oracle::set_oracle_key(Origin::signed(20), 50, TestSignature(50)),
+				"key is used by another oracle"
+			);
+		});
+	}
+
+	#[test]
+	fn rewards_are_claimed_after_their_era() {
+		with_externalities(&mut new_test_ext(), || {
+			earn_reward(30);
+			assert_eq!(Oracle::eras_rewards(&(0, 1)), 30);
+			assert_eq!(Oracle::eras_rewarded(0), vec![1]);
+			assert_eq!(Oracle::eras_total_rewards(0), 30);
+			assert_eq!(Balances::free_balance(&1), 1000);
+			assert_noop!(Oracle::payout_era(Origin::signed(2), 1, 0), "era is not finished");
+
+			Oracle::new_era();
+			assert_noop!(Oracle::payout_era(Origin::signed(2), 2, 0), "no reward to claim");
+			assert_ok!(Oracle::payout_era(Origin::signed(2), 1, 0));
+			assert_eq!(Balances::free_balance(&1), 1030);
+			assert_eq!(Oracle::performance(10).paid, 30);
+			assert_eq!(Oracle::eras_rewards(&(0, 1)), 0);
+			assert_noop!(Oracle::payout_era(Origin::signed(2), 1, 0), "no reward to claim");
+		});
+	}
+
+	#[test]
+	fn rewards_are_pruned_out_of_history() {
+		with_externalities(&mut new_test_ext(), || {
+			earn_reward(30);
+			for _ in 0..84 {
+				Oracle::new_era();
+			}
+			assert_eq!(Oracle::current_era_index(), 84);
+			assert_eq!(Oracle::eras_rewards(&(0, 1)), 30);
+
+			Oracle::new_era();
+			assert_eq!(Oracle::eras_rewards(&(0, 1)), 0);
+			assert_eq!(Oracle::eras_rewarded(0), Vec::<u64>::new());
+			assert_eq!(Oracle::eras_total_rewards(0), 0);
+			assert_noop!(Oracle::payout_era(Origin::signed(2), 1, 0), "era is out of history");
+		});
+	}
+
+	#[test]
+	fn accuracy_weighted_reward_splits_in_proportion() {
+		let split = |accuracies: &[(u64, u32)]| {
+			let accuracies: Vec<(u64, Perbill)> = accuracies
+				.iter()
+				.map(|&(who, percent)| (who, Perbill::from_percent(percent)))
+				.collect();
+			<AccuracyWeightedReward as RewardPolicy<u64, u64>>::distribute(100, &accuracies)
+		};
+
+		assert_eq!(split(&[(10, 80), (20, 20)]), vec![(10, 80), (20, 20)]);
+		assert_eq!(split(&[(10, 100), (20, 50), (30, 50)]), vec![(10, 50), (20, 25), (30, 25)]);
+		// Only relative accuracy matters.
+		assert_eq!(split(&[(10, 40), (20, 10)]), vec![(10, 80), (20, 20)]);
+		assert_eq!(split(&[(10, 100), (20, 0)]), vec![(10, 100), (20, 0)]);
+		// Nothing is paid if all reports were inaccurate.
+		assert_eq!(split(&[(10, 0), (20, 0)]), vec![]);
+		assert_eq!(split(&[]), vec![]);
+	}
+
+	#[test]
+	fn rewards_are_capped_by_service_budget() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Oracle::register_service(Origin::ROOT, 0, ServiceInfo {
+				fee: 30,
+				miss_report_slash: 0,
+				reward_budget: 50,
+			}));
+			set_oracles(&[(1, 10, 100), (2, 20, 100)]);
+			system::Module::<Test>::set_block_number(5);
+			<Oracle as OracleMixedIn<Test>>::on_witnessed(&10, 0, 0);
+			<Oracle as OracleMixedIn<Test>>::on_witnessed(&20, 0, 0);
+
+			// Fees of 60 are cut down to the budget of 50.
+			Oracle::slash_and_reward_oracles(11);
+			assert_eq!(Oracle::eras_rewards(&(0, 1)), 25);
+			assert_eq!(Oracle::eras_rewards(&(0, 2)), 25);
+			assert_eq!(Oracle::service_paid(0), (0, 50));
+
+			// The budget is spent for the rest of the era.
+			system::Module::<Test>::set_block_number(16);
+			<Oracle as OracleMixedIn<Test>>::on_witnessed(&10, 0, 0);
+			<Oracle as OracleMixedIn<Test>>::on_witnessed(&20, 0, 0);
+			Oracle::slash_and_reward_oracles(22);
+			assert_eq!(Oracle::eras_total_rewards(0), 50);
+			assert_eq!(Oracle::oracle_last_rewarded(&(10, 0, 0)), 22);
+
+			// It's available again in the next era.
+			<CurrentEra<Test>>::put(25);
+			Oracle::new_era();
+			system::Module::<Test>::set_block_number(27);
+			<Oracle as OracleMixedIn<Test>>::on_witnessed(&10, 0, 0);
+			<Oracle as OracleMixedIn<Test>>::on_witnessed(&20, 0, 0);
+			Oracle::slash_and_reward_oracles(33);
+			assert_eq!(Oracle::eras_total_rewards(1), 50);
+		});
 	}
 
 	#[test]
-	fn it_works_for_default_value() {
+	fn rewards_are_paid_to_payee() {
 		with_externalities(&mut new_test_ext(), || {
-			// Just a dummy test for the dummy funtion `do_something`
-			// calling the `do_something` function with a value 42
-			assert_ok!(TemplateModule::do_something(Origin::signed(1), 42));
-			// asserting that the stored value is equal to what we stored
-			assert_eq!(TemplateModule::something(), Some(42));
+			earn_reward(30);
+			Oracle::new_era();
+			assert_noop!(Oracle::set_payee(Origin::signed(1), RewardDestination::Staked), "not a controller");
+			assert_eq!(Oracle::payee(1), RewardDestination::Stash);
+
+			assert_ok!(Oracle::set_payee(Origin::signed(10), RewardDestination::Staked));
+			assert_ok!(Oracle::payout_era(Origin::signed(2), 1, 0));
+			assert_eq!(Balances::free_balance(&1), 1030);
+			assert_eq!(Oracle::oracle_ledger(10).unwrap().active, 130);
+			assert_eq!(Balances::locks(1)[0].amount, 130);
+
+			<ErasRewards<Test>>::insert(&(0, 1), 20);
+			assert_ok!(Oracle::set_payee(Origin::signed(10), RewardDestination::Account(50)));
+			assert_ok!(Oracle::payout_era(Origin::signed(2), 1, 0));
+			assert_eq!(Balances::free_balance(&1), 1030);
+			assert_eq!(Balances::free_balance(&50), 20);
+			assert_eq!(Oracle::performance(10).paid, 50);
+		});
+	}
+
+	#[test]
+	fn rebond_takes_most_recent_unbonds_first() {
+		with_externalities(&mut new_test_ext(), || {
+			system::Module::<Test>::set_block_number(1);
+			assert_ok!(Oracle::bond(Origin::signed(1), 10, 100));
+			assert_noop!(Oracle::rebond(Origin::signed(10), 10), "no unbonding amount");
+			assert_ok!(Oracle::unbind(Origin::signed(10), 10));
+			system::Module::<Test>::set_block_number(2);
+			assert_ok!(Oracle::unbind(Origin::signed(10), 20));
+			assert_eq!(Oracle::candidates(), Vec::<u64>::new());
+
+			assert_ok!(Oracle::rebond(Origin::signed(10), 25));
+			let ledger = Oracle::oracle_ledger(10).unwrap();
+			assert_eq!(ledger.active, 95);
+			assert_eq!(ledger.unbonds, vec![unbind(5, 101)]);
+			assert_eq!(Oracle::candidates(), vec![10]);
+
+			// Only the amount being unbonded is bonded back.
+			assert_ok!(Oracle::rebond(Origin::signed(10), 100));
+			let ledger = Oracle::oracle_ledger(10).unwrap();
+			assert_eq!(ledger.active, 100);
+			assert_eq!(ledger.unbonds, vec![]);
+			assert_eq!(Balances::locks(1)[0].amount, 100);
+			assert_noop!(Oracle::rebond(Origin::signed(10), 10), "no unbonding amount");
+		});
+	}
+
+	#[test]
+	fn rebond_keeps_jailed_oracles_out_of_candidates() {
+		with_externalities(&mut new_test_ext(), || {
+			system::Module::<Test>::set_block_number(1);
+			assert_ok!(Oracle::bond(Origin::signed(1), 10, 100));
+			assert_ok!(Oracle::unbind(Origin::signed(10), 10));
+			<Jailed<Test>>::insert(10, 20);
+
+			assert_ok!(Oracle::rebond(Origin::signed(10), 10));
+			assert_eq!(Oracle::oracle_ledger(10).unwrap().active, 100);
+			assert_eq!(Oracle::candidates(), Vec::<u64>::new());
 		});
 	}
 }
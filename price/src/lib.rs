@@ -1,23 +1,124 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use codec::{Decode, Encode};
-use oracle::OracleMixedIn;
+use oracle::{DutyId, OracleDuties, OracleMixedIn, ServiceId};
 use rstd::prelude::*;
-use sr_primitives::traits::{Bounded, CheckedAdd, CheckedSub, EnsureOrigin, Zero, OnFinalize};
+use sr_primitives::traits::{
+    Bounded, CheckedAdd, CheckedSub, EnsureOrigin, One, Zero, OnFinalize, SaturatedConversion,
+    Saturating, Verify,
+};
+use sr_primitives::transaction_validity::{
+    TransactionLongevity, TransactionPriority, TransactionValidity, ValidTransaction,
+};
+use sr_primitives::{ApplyError, Perbill};
 use support::traits::{
     ChangeMembers, Currency, Get, LockIdentifier, LockableCurrency, ReservableCurrency,
     WithdrawReasons
 };
-use support::{decl_event, decl_module, decl_storage, dispatch::Result, StorageMap, StorageValue};
-use system::{ensure_root, ensure_signed};
+use support::{decl_event, decl_module, decl_storage, dispatch::Result, Parameter, StorageMap, StorageValue};
+use system::{ensure_none, ensure_root, ensure_signed};
+
+mod migration;
+mod musig;
+#[cfg(test)]
+mod price_test;
+
+pub use musig::{Sr25519MuSig, MUSIG_CONTEXT};
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+type AggregateSignatureOf<T> = AggregateSignature<
+    <<T as Trait>::SignatureAggregation as SignatureAggregation<<T as system::Trait>::AccountId>>::Signature,
+>;
+
+pub type Price = u128;
+
+/// Identifier of a price feed, which is also the duty of reporting it in the oracle module.
+pub type FeedId = DutyId;
+
+/// Identifier of a price request.
+pub type RequestId = u64;
 
-type Price = u128;
+/// Reporting round of prices, `RoundLength` blocks each and starting at 1.
+/// Each oracle can report a feed once per round.
+pub type Round = u64;
 
-pub trait Trait: system::Trait {
+/// Context prepended to attestations before they're signed.
+pub const ATTESTATION_CONTEXT: &[u8] = b"price-attestation";
+
+pub trait Trait: system::Trait + timestamp::Trait {
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
 
     type OracleMixedIn: OracleMixedIn<Self>;
+    /// The service id this module is registered with in the oracle module.
+    type ServiceId: Get<ServiceId>;
     type ReportOrigin: EnsureOrigin<Self::Origin, Success = Self::AccountId>;
+
+    /// Currency type of price request fees.
+    type Currency: ReservableCurrency<Self::AccountId>;
+
+    /// The duration in which oracles should answer a price request.
+    type RequestPeriod: Get<Self::BlockNumber>;
+
+    /// The count of answers needed to fulfill a price request.
+    type MinAnswers: Get<u32>;
+
+    /// The minimum fee of a price request answered by oracles.
+    type MinRequestFee: Get<BalanceOf<Self>>;
+
+    /// The maximum count of open price requests.
+    type MaxOpenRequests: Get<u32>;
+
+    /// The consumer notified when a price request is fulfilled or unanswered.
+    type PriceConsumer: PriceConsumer<Self::AccountId>;
+
+    /// The deviation in basis points over which a new price is committed,
+    /// for feeds without their own update policy.
+    type DefaultDeviation: Get<u32>;
+
+    /// The duration after which a new price is committed even if it didn't deviate,
+    /// for feeds without their own update policy.
+    type DefaultHeartbeat: Get<Self::BlockNumber>;
+
+    /// The move in basis points in one round over which a feed is frozen,
+    /// for feeds without their own update policy.
+    type DefaultCircuitBreaker: Get<u32>;
+
+    /// The origin that's able to unfreeze a feed, besides root.
+    type UnfreezeOrigin: EnsureOrigin<Self::Origin>;
+
+    /// Signature type of off-chain price attestations, signed by oracle accounts.
+    type Signature: Verify<Signer = Self::AccountId> + Parameter;
+
+    /// The proportion of oracles that should sign a price of feeds in threshold mode.
+    type SignatureThreshold: Get<Perbill>;
+
+    /// How signatures of oracles over a price of feeds in threshold mode aggregate into one.
+    type SignatureAggregation: SignatureAggregation<Self::AccountId>;
+
+    /// The priority of unsigned price reports in the transaction pool.
+    type UnsignedPriority: Get<TransactionPriority>;
+
+    /// The count of blocks an unsigned price report stays valid in the transaction pool.
+    type UnsignedLongevity: Get<TransactionLongevity>;
+
+    /// The duration of a reporting round.
+    type RoundLength: Get<Self::BlockNumber>;
+
+    /// The feed that the price of version 0, before feeds were introduced, is migrated to.
+    type LegacyFeed: Get<FeedId>;
+}
+
+/// Consumer module should implement this trait to receive requested prices.
+pub trait PriceConsumer<AccountId> {
+    /// A price request is fulfilled with the aggregated price.
+    fn on_price(id: RequestId, requester: &AccountId, feed: FeedId, price: Price);
+    /// A price request is unanswered, its fee is refunded.
+    fn on_unanswered(id: RequestId, requester: &AccountId, feed: FeedId);
+}
+
+impl<AccountId> PriceConsumer<AccountId> for () {
+    fn on_price(_: RequestId, _: &AccountId, _: FeedId, _: Price) {}
+    fn on_unanswered(_: RequestId, _: &AccountId, _: FeedId) {}
 }
 
 #[derive(PartialEq, Eq, Clone, Encode, Decode)]
@@ -27,10 +128,156 @@ pub struct PriceReport<AccountId> {
     price: Price,
 }
 
+/// A price of a feed attested by one oracle off chain.
+#[derive(PartialEq, Eq, Clone, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Attestation {
+    pub feed: FeedId,
+    pub price: Price,
+    pub round: Round,
+}
+
+impl Attestation {
+    /// The payload that oracles should sign.
+    pub fn signing_payload(&self) -> Vec<u8> {
+        (ATTESTATION_CONTEXT, self).encode()
+    }
+}
+
+/// An attestation with its oracle's signature.
+#[derive(PartialEq, Eq, Clone, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct SignedAttestation<AccountId, Signature> {
+    pub attestation: Attestation,
+    pub signer: AccountId,
+    pub signature: Signature,
+}
+
+/// Signatures of several keys over the same payload, aggregated into one signature.
+pub trait SignatureAggregation<AccountId> {
+    /// The aggregated signature.
+    type Signature: Parameter;
+
+    /// Verify a signature aggregated from those of all of `keys` over `payload`.
+    fn verify(keys: &[AccountId], payload: &[u8], signature: &Self::Signature) -> bool;
+}
+
+/// One signature of a threshold of oracles over the same attestation, aggregated off chain.
+/// Its size doesn't grow with the count of signers, besides their indices.
+#[derive(PartialEq, Eq, Clone, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct AggregateSignature<Signature> {
+    /// Indices of signers in the acting oracles, strictly increasing.
+    pub signers: Vec<u32>,
+    /// The signature aggregated from those of all signers.
+    pub signature: Signature,
+}
+
+impl<Signature> AggregateSignature<Signature> {
+    /// Verify that at least `required` of `keys` signed `payload`, aggregated by `A`.
+    pub fn verify<AccountId, A>(&self, keys: &[AccountId], payload: &[u8], required: usize) -> Result
+    where
+        AccountId: Clone,
+        A: SignatureAggregation<AccountId, Signature = Signature>,
+    {
+        if self.signers.len() < required {
+            return Err("not enough signatures");
+        }
+        if self.signers.windows(2).any(|w| w[0] >= w[1]) {
+            return Err("signers are not strictly increasing");
+        }
+
+        let mut signer_keys = Vec::with_capacity(self.signers.len());
+        for index in &self.signers {
+            signer_keys.push(keys.get(*index as usize).ok_or("signer is not an oracle")?.clone());
+        }
+        if !A::verify(&signer_keys, payload, &self.signature) {
+            return Err("bad aggregate signature");
+        }
+        Ok(())
+    }
+}
+
+/// How oracles submit prices of a feed.
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum SubmissionMode {
+    /// Oracles report prices one by one, the median is taken on chain.
+    Reports,
+    /// Oracles agree on a price off chain and submit it with an aggregate signature of a threshold of them.
+    Threshold,
+}
+
+impl Default for SubmissionMode {
+    fn default() -> Self {
+        SubmissionMode::Reports
+    }
+}
+
+/// Committed price of a feed, with when it was last updated.
+#[derive(PartialEq, Eq, Clone, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct FeedPrice<BlockNumber, Moment> {
+    pub price: Price,
+    pub updated_at: BlockNumber,
+    pub timestamp: Moment,
+}
+
+/// When a new aggregated price of a feed should be committed.
+#[derive(PartialEq, Eq, Clone, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct UpdatePolicy<BlockNumber> {
+    /// Commit when price moves more than this many basis points.
+    pub deviation: u32,
+    /// Commit when this duration has passed since the last update, zero to disable.
+    pub heartbeat: BlockNumber,
+    /// Freeze the feed when price moves more than this many basis points in one round, zero to disable.
+    pub circuit_breaker: u32,
+}
+
+/// A price request waiting for oracles to answer.
+#[derive(PartialEq, Eq, Clone, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct PriceRequest<AccountId, Balance, BlockNumber> {
+    requester: AccountId,
+    feed: FeedId,
+    fee: Balance,
+    deadline: BlockNumber,
+    answers: Vec<PriceReport<AccountId>>,
+}
+
 decl_storage! {
     trait Store for Module<T: Trait> as PriceStorate {
-        CurrentPrice get(current_price): Price;
-        PriceReports get(price_reports): Vec<PriceReport<T::AccountId>>;
+        /// Price feeds reported by oracles.
+        Feeds get(feeds) config(): Vec<FeedId>;
+        /// Committed price of each feed, with when it was last updated.
+        CurrentPrice get(current_price): map FeedId => Option<FeedPrice<T::BlockNumber, T::Moment>>;
+        /// Update policies of feeds, default ones are used if absent.
+        UpdatePolicies get(update_policies): map FeedId => Option<UpdatePolicy<T::BlockNumber>>;
+        /// Feeds frozen by the circuit breaker, and the height they were frozen at.
+        FrozenFeeds get(frozen_feeds): map FeedId => Option<T::BlockNumber>;
+        PriceReports get(price_reports): map FeedId => Vec<PriceReport<T::AccountId>>;
+
+        /// Last reported round of each oracle for a feed.
+        LastRound get(last_round): map (FeedId, T::AccountId) => Round;
+        /// Count of accepted price submissions of each oracle.
+        SubmissionCount get(submission_count): map T::AccountId => u64;
+        /// Last round in which reports of a feed were scored against their median.
+        LastScoredRound get(last_scored_round): map FeedId => Round;
+        /// Submission mode of each feed.
+        FeedModes get(feed_mode): map FeedId => SubmissionMode;
+        /// Last round of each feed agreed by a threshold of oracles.
+        LastAgreedRound get(last_agreed_round): map FeedId => Round;
+
+        /// Id of the next price request.
+        NextRequestId get(next_request_id): RequestId;
+        /// Open price requests.
+        Requests get(request): map RequestId => Option<PriceRequest<T::AccountId, BalanceOf<T>, T::BlockNumber>>;
+        /// Ids of open price requests.
+        OpenRequests get(open_requests): Vec<RequestId>;
+
+        /// Version of the storage layout, migrated in `on_initialize` after a runtime upgrade.
+        StorageVersion get(storage_version) build(|_| migration::STORAGE_VERSION): u32;
     }
 }
 
@@ -38,65 +285,607 @@ decl_module! {
     pub struct Module<T: Trait> for enum Call where origin: T::Origin {
         fn deposit_event() = default;
 
-        pub fn report(origin, price: Price) -> Result{
+        const RequestPeriod: T::BlockNumber = T::RequestPeriod::get();
+        const MinAnswers: u32 = T::MinAnswers::get();
+        const MinRequestFee: BalanceOf<T> = T::MinRequestFee::get();
+        const MaxOpenRequests: u32 = T::MaxOpenRequests::get();
+        const DefaultDeviation: u32 = T::DefaultDeviation::get();
+        const DefaultHeartbeat: T::BlockNumber = T::DefaultHeartbeat::get();
+        const DefaultCircuitBreaker: u32 = T::DefaultCircuitBreaker::get();
+        const SignatureThreshold: Perbill = T::SignatureThreshold::get();
+        const RoundLength: T::BlockNumber = T::RoundLength::get();
+        const LegacyFeed: FeedId = T::LegacyFeed::get();
+
+        fn on_initialize() {
+            if Self::storage_version() < migration::STORAGE_VERSION {
+                migration::migrate::<T>();
+            }
+        }
+
+        /// report a price of a feed, once per round.
+        pub fn report(origin, feed: FeedId, price: Price) -> Result{
             let who = T::ReportOrigin::ensure_origin(origin)?;
-            Self::add_price(who, price);
+            if !Self::feeds().contains(&feed) {
+                return Err("unknown feed");
+            }
+            if Self::feed_mode(feed) != SubmissionMode::Reports {
+                return Err("feed only accepts aggregate signatures");
+            }
+
+            let key = (feed, who);
+            let round = Self::current_round();
+            Self::check_round(Self::last_round(&key), round)?;
+            <LastRound<T>>::insert(&key, round);
+            Self::add_price(key.1, feed, price)
+        }
+
+        /// submit a bundle of prices signed off chain by oracles, in one transaction.
+        pub fn submit_aggregate(origin, bundle: Vec<SignedAttestation<T::AccountId, T::Signature>>) -> Result{
+            let who = ensure_signed(origin)?;
+            if bundle.is_empty() {
+                return Err("empty bundle");
+            }
+
+            let oracles = T::OracleMixedIn::oracles();
+            // Verify the whole bundle before storing anything, rounds must increase within it too.
+            let mut rounds: Vec<((FeedId, T::AccountId), Round)> = Vec::new();
+            for signed in &bundle {
+                let attestation = &signed.attestation;
+                let oracle = Self::verify_attestation(signed, &oracles)?;
+
+                let key = (attestation.feed, oracle);
+                let last_round = rounds
+                    .iter()
+                    .rev()
+                    .find(|(k, _)| *k == key)
+                    .map(|(_, round)| *round)
+                    .unwrap_or_else(|| Self::last_round(&key));
+                Self::check_round(last_round, attestation.round)?;
+                rounds.push((key, attestation.round));
+            }
+
+            let count = bundle.len() as u32;
+            for (signed, ((feed, oracle), round)) in bundle.into_iter().zip(rounds.into_iter()) {
+                <LastRound<T>>::insert(&(feed, oracle.clone()), round);
+                Self::add_price(oracle, feed, signed.attestation.price)?;
+            }
+
+            Self::deposit_event(RawEvent::AggregateSubmitted(who, count));
             Ok(())
         }
 
-        fn on_finalize() {
+        /// report a price signed off chain by an oracle, without transaction fee.
+        /// It's validated by `ValidateUnsigned` before entering the transaction pool.
+        pub fn report_unsigned(origin, signed: SignedAttestation<T::AccountId, T::Signature>) -> Result{
+            ensure_none(origin)?;
+            let oracle = Self::verify_attestation(&signed, &T::OracleMixedIn::oracles())?;
+
+            let attestation = signed.attestation;
+            let key = (attestation.feed, oracle);
+            Self::check_round(Self::last_round(&key), attestation.round)?;
+            <LastRound<T>>::insert(&key, attestation.round);
+            Self::add_price(key.1, attestation.feed, attestation.price)
+        }
+
+        /// submit a price of a feed in threshold mode, with one signature aggregated from those of
+        /// a threshold of oracles.
+        pub fn submit_threshold(origin, attestation: Attestation, signature: AggregateSignatureOf<T>) -> Result{
+            let _ = ensure_signed(origin)?;
+            let feed = attestation.feed;
+            if !Self::feeds().contains(&feed) {
+                return Err("unknown feed");
+            }
+            if Self::feed_mode(feed) != SubmissionMode::Threshold {
+                return Err("feed doesn't accept aggregate signatures");
+            }
+            Self::check_round(Self::last_agreed_round(feed), attestation.round)?;
+
+            let oracles = T::OracleMixedIn::oracles();
+            let required = required_signers(T::SignatureThreshold::get(), oracles.len());
+            let keys: Vec<T::AccountId> = oracles.iter().map(T::OracleMixedIn::oracle_key).collect();
+            signature.verify::<_, T::SignatureAggregation>(&keys, &attestation.signing_payload(), required)?;
+
+            LastAgreedRound::insert(feed, attestation.round);
+            signature.signers.iter().for_each(|index| {
+                let who = &oracles[*index as usize];
+                <SubmissionCount<T>>::mutate(who, |count| *count = count.saturating_add(1));
+                Self::answer_requests(who, feed, attestation.price);
+                T::OracleMixedIn::on_witnessed(who, T::ServiceId::get(), feed);
+            });
+            Self::apply_price(feed, attestation.price, <system::Module<T>>::block_number());
+
+            Self::deposit_event(RawEvent::ThresholdSubmitted(feed, attestation.round, attestation.price));
+            Ok(())
+        }
+
+        /// set how oracles submit prices of a feed.
+        pub fn set_feed_mode(origin, feed: FeedId, mode: SubmissionMode) -> Result{
+            ensure_root(origin)?;
+            if !Self::feeds().contains(&feed) {
+                return Err("unknown feed");
+            }
+            FeedModes::insert(feed, mode);
+            <PriceReports<T>>::remove(feed);
+            Self::deposit_event(RawEvent::FeedModeSet(feed, mode));
+            Ok(())
+        }
+
+        /// request a fresh price of a feed, it's answered at once if current price
+        /// is not older than `max_age`, otherwise by oracles within `RequestPeriod`.
+        pub fn request_price(origin, feed: FeedId, max_age: T::BlockNumber, fee: BalanceOf<T>) -> Result{
+            let who = ensure_signed(origin)?;
+            if !Self::feeds().contains(&feed) {
+                return Err("unknown feed");
+            }
+
+            if Self::is_frozen(feed) {
+                return Err("feed is frozen");
+            }
+
             let block_number = <system::Module<T>>::block_number();
-            let old_price = Self::current_price();
-            let mut prices: Vec<Price> = Self::price_reports().iter().map(|x| x.price).collect();
-            if prices.len() > 0 {
-                let median_price = median(&mut prices);
+            let id = Self::next_request_id();
+            let fresh = Self::current_price(feed)
+                .filter(|current| block_number <= current.updated_at + max_age);
+            if let Some(current) = fresh {
+                NextRequestId::put(id + 1);
+                T::PriceConsumer::on_price(id, &who, feed, current.price);
+                Self::deposit_event(RawEvent::PriceRequestFulfilled(id, current.price));
+                return Ok(());
+            }
 
-                if old_price != median_price {
-                    CurrentPrice::put(median_price);
-                    Self::deposit_event(RawEvent::PriceChanged(median_price));
-                }
+            // Unanswered requests count misses of oracles, so they can't be free nor unbounded.
+            if fee < T::MinRequestFee::get() {
+                return Err("request fee is too small");
+            }
+            if Self::open_requests().len() >= T::MaxOpenRequests::get() as usize {
+                return Err("too many open requests");
             }
+            T::Currency::reserve(&who, fee)?;
+            NextRequestId::put(id + 1);
+            <Requests<T>>::insert(id, PriceRequest {
+                requester: who.clone(),
+                feed: feed,
+                fee: fee,
+                deadline: block_number + T::RequestPeriod::get(),
+                answers: vec![],
+            });
+            OpenRequests::mutate(|ids| ids.push(id));
+
+            Self::deposit_event(RawEvent::PriceRequested(id, who, feed, fee));
+            Ok(())
+        }
+
+        /// add a price feed to be reported by oracles.
+        pub fn add_feed(origin, feed: FeedId) -> Result{
+            ensure_root(origin)?;
+            let mut feeds = Self::feeds();
+            if feeds.contains(&feed) {
+                return Err("feed already exists");
+            }
+            feeds.push(feed);
+            Feeds::put(feeds);
+            T::OracleMixedIn::on_duty_added(T::ServiceId::get(), feed);
+            Self::deposit_event(RawEvent::FeedAdded(feed));
+            Ok(())
+        }
 
-            let reports: Vec<PriceReport<T::AccountId>> = Self::price_reports()
-                .into_iter()
-                .filter(|x| T::OracleMixedIn::is_valid(&x.reporter))
-                .clone()
-                .collect();
+        /// remove a price feed.
+        pub fn remove_feed(origin, feed: FeedId) -> Result{
+            ensure_root(origin)?;
+            let mut feeds = Self::feeds();
+            if !feeds.contains(&feed) {
+                return Err("unknown feed");
+            }
+            feeds.retain(|x| *x != feed);
+            Feeds::put(feeds);
+            <CurrentPrice<T>>::remove(feed);
+            <UpdatePolicies<T>>::remove(feed);
+            <FrozenFeeds<T>>::remove(feed);
+            FeedModes::remove(feed);
+            LastAgreedRound::remove(feed);
+            LastScoredRound::remove(feed);
+            <PriceReports<T>>::remove(feed);
+            Self::cancel_requests(feed);
+            Self::deposit_event(RawEvent::FeedRemoved(feed));
+            Ok(())
+        }
 
-            <PriceReports<T>>::put(reports);
+        /// set the update policy of a price feed.
+        pub fn set_update_policy(origin, feed: FeedId, policy: UpdatePolicy<T::BlockNumber>) -> Result{
+            ensure_root(origin)?;
+            if !Self::feeds().contains(&feed) {
+                return Err("unknown feed");
+            }
+            <UpdatePolicies<T>>::insert(feed, policy);
+            Self::deposit_event(RawEvent::UpdatePolicySet(feed));
+            Ok(())
+        }
+
+        /// unfreeze a feed frozen by the circuit breaker, accepting its current aggregated price.
+        pub fn unfreeze_feed(origin, feed: FeedId) -> Result{
+            T::UnfreezeOrigin::try_origin(origin)
+                .map(|_| ())
+                .or_else(ensure_root)
+                .map_err(|_| "bad origin")?;
+            if !Self::is_frozen(feed) {
+                return Err("feed is not frozen");
+            }
+
+            <FrozenFeeds<T>>::remove(feed);
+            let mut prices: Vec<Price> = Self::price_reports(feed).iter().map(|x| x.price).collect();
+            if prices.len() > 0 {
+                Self::commit_price(feed, median(&mut prices), <system::Module<T>>::block_number());
+            }
+            Self::deposit_event(RawEvent::FeedUnfrozen(feed));
+            Ok(())
+        }
+
+        fn on_finalize() {
+            let block_number = <system::Module<T>>::block_number();
+            Self::feeds().into_iter().for_each(|feed| Self::update_price(feed, block_number));
+            Self::settle_requests(block_number);
         }
     }
 }
 
 impl<T: Trait> Module<T> {
-    fn add_price(who: T::AccountId, price: Price) -> Result {
-        let price_reports = Self::price_reports();
-        let mut found = false;
-        let mut price_reports: Vec<PriceReport<T::AccountId>> = price_reports.into_iter().map(|x| {
-            if x.reporter == who {
-                let mut new_report = x;
-                new_report.price = price;
-                found = true;
-                new_report
-            }else{
-                x
-            }
-        }).collect();
-
-        if !found {
-            price_reports.push(PriceReport {
-                reporter: who.clone(),
-                price: price,
+    fn update_price(feed: FeedId, block_number: T::BlockNumber) {
+        let mut prices: Vec<Price> = Self::price_reports(feed).iter().map(|x| x.price).collect();
+        if prices.len() > 0 {
+            let median_price = median(&mut prices);
+            Self::apply_price(feed, median_price, block_number);
+        }
+        // Reports of a round are scored once it closes, at its last block.
+        if Self::blocks_left_in_round().is_one() {
+            Self::score_reports(feed);
+        }
+
+        let reports: Vec<PriceReport<T::AccountId>> = Self::price_reports(feed)
+            .into_iter()
+            .filter(|x| T::OracleMixedIn::is_valid(&x.reporter, T::ServiceId::get(), feed))
+            .clone()
+            .collect();
+
+        <PriceReports<T>>::insert(feed, reports);
+    }
+
+    /// Tell oracle module how accurate reports of the current round are against their median,
+    /// once per round.
+    fn score_reports(feed: FeedId) {
+        let round = Self::current_round();
+        if round <= Self::last_scored_round(feed) {
+            return;
+        }
+        let reports: Vec<PriceReport<T::AccountId>> = Self::price_reports(feed)
+            .into_iter()
+            .filter(|x| Self::last_round(&(feed, x.reporter.clone())) == round)
+            .collect();
+        if reports.is_empty() {
+            return;
+        }
+        LastScoredRound::insert(feed, round);
+
+        let mut prices: Vec<Price> = reports.iter().map(|x| x.price).collect();
+        let median_price = median(&mut prices);
+        reports.iter().for_each(|report| {
+            let bps = deviation(median_price, report.price);
+            T::OracleMixedIn::on_deviation(&report.reporter, T::ServiceId::get(), feed, bps);
+            T::OracleMixedIn::on_accuracy(&report.reporter, T::ServiceId::get(), feed, accuracy(bps));
+        });
+    }
+
+    /// Commit an aggregated price according to the feed's update policy.
+    fn apply_price(feed: FeedId, price: Price, block_number: T::BlockNumber) {
+        if Self::is_frozen(feed) {
+            return;
+        }
+        let policy = Self::update_policy(feed);
+
+        match Self::current_price(feed) {
+            Some(ref current)
+                if policy.circuit_breaker > 0
+                    && exceeds_deviation(current.price, price, policy.circuit_breaker) =>
+            {
+                <FrozenFeeds<T>>::insert(feed, block_number);
+                Self::deposit_event(RawEvent::FeedFrozen(feed, current.price, price));
+            }
+            Some(current) => {
+                if exceeds_deviation(current.price, price, policy.deviation)
+                    || (!policy.heartbeat.is_zero()
+                        && block_number >= current.updated_at + policy.heartbeat)
+                {
+                    Self::commit_price(feed, price, block_number);
+                }
+            }
+            None => Self::commit_price(feed, price, block_number),
+        }
+    }
+
+    fn commit_price(feed: FeedId, price: Price, block_number: T::BlockNumber) {
+        <CurrentPrice<T>>::insert(feed, FeedPrice {
+            price: price,
+            updated_at: block_number,
+            timestamp: <timestamp::Module<T>>::now(),
+        });
+        Self::deposit_event(RawEvent::PriceChanged(feed, price));
+    }
+
+    /// Update policy of a feed, falling back to the default one.
+    pub fn update_policy(feed: FeedId) -> UpdatePolicy<T::BlockNumber> {
+        Self::update_policies(feed).unwrap_or_else(|| UpdatePolicy {
+            deviation: T::DefaultDeviation::get(),
+            heartbeat: T::DefaultHeartbeat::get(),
+            circuit_breaker: T::DefaultCircuitBreaker::get(),
+        })
+    }
+
+    /// Predicate if a feed is frozen by the circuit breaker.
+    pub fn is_frozen(feed: FeedId) -> bool {
+        <FrozenFeeds<T>>::exists(feed)
+    }
+
+    fn settle_requests(block_number: T::BlockNumber) {
+        let min_answers = rstd::cmp::max(T::MinAnswers::get(), 1) as usize;
+        let (done, open): (Vec<RequestId>, Vec<RequestId>) = Self::open_requests()
+            .into_iter()
+            .partition(|id| match Self::request(id) {
+                // Requests of frozen feeds wait until their deadline.
+                Some(ref request) if Self::is_frozen(request.feed) => block_number > request.deadline,
+                Some(request) => request.answers.len() >= min_answers || block_number > request.deadline,
+                None => true,
             });
+        if done.is_empty() {
+            return;
         }
+        OpenRequests::put(open);
+
+        done.into_iter().for_each(|id| {
+            let request = match <Requests<T>>::take(id) {
+                Some(request) => request,
+                None => return,
+            };
 
-        <PriceReports<T>>::put(price_reports);
+            let frozen = Self::is_frozen(request.feed);
+            if !frozen && request.answers.len() >= min_answers {
+                let mut prices: Vec<Price> = request.answers.iter().map(|x| x.price).collect();
+                let price = median(&mut prices);
+                Self::pay_answers(&request);
+                T::PriceConsumer::on_price(id, &request.requester, request.feed, price);
+                Self::deposit_event(RawEvent::PriceRequestFulfilled(id, price));
+            } else {
+                T::Currency::unreserve(&request.requester, request.fee);
+                // Unanswered requests are held against oracles that didn't answer,
+                // unless the feed is frozen.
+                if !frozen {
+                    T::OracleMixedIn::oracles()
+                        .iter()
+                        .filter(|o| !request.answers.iter().any(|a| &a.reporter == *o))
+                        .for_each(|o| T::OracleMixedIn::on_missed(o, T::ServiceId::get(), request.feed));
+                }
+                T::PriceConsumer::on_unanswered(id, &request.requester, request.feed);
+                Self::deposit_event(RawEvent::PriceRequestUnanswered(id));
+            }
+        });
+    }
 
-        T::OracleMixedIn::on_witnessed(&who);
-        Self::deposit_event(RawEvent::PriceReported(who, price));
+    /// Refund open requests of a removed feed, which nothing can answer anymore.
+    fn cancel_requests(feed: FeedId) {
+        let (cancelled, open): (Vec<RequestId>, Vec<RequestId>) = Self::open_requests()
+            .into_iter()
+            .partition(|id| Self::request(id).map_or(true, |request| request.feed == feed));
+        if cancelled.is_empty() {
+            return;
+        }
+        OpenRequests::put(open);
+
+        cancelled.into_iter().for_each(|id| {
+            if let Some(request) = <Requests<T>>::take(id) {
+                T::Currency::unreserve(&request.requester, request.fee);
+                T::PriceConsumer::on_unanswered(id, &request.requester, feed);
+                Self::deposit_event(RawEvent::PriceRequestCancelled(id));
+            }
+        });
+    }
+
+    /// Split the fee among oracles that answered, the remainder goes back to the requester.
+    fn pay_answers(request: &PriceRequest<T::AccountId, BalanceOf<T>, T::BlockNumber>) {
+        let share = request.fee / BalanceOf::<T>::from(request.answers.len() as u32);
+        let mut remaining = request.fee;
+        if !share.is_zero() {
+            request.answers.iter().for_each(|a| {
+                if T::Currency::repatriate_reserved(&request.requester, &a.reporter, share).is_ok() {
+                    remaining = remaining.saturating_sub(share);
+                }
+            });
+        }
+        T::Currency::unreserve(&request.requester, remaining);
+    }
+
+    fn add_price(who: T::AccountId, feed: FeedId, price: Price) -> Result {
+        let mut price_reports = Self::price_reports(feed);
+        insert_report(&mut price_reports, &who, price);
+        <PriceReports<T>>::insert(feed, price_reports);
+        <SubmissionCount<T>>::mutate(&who, |count| *count = count.saturating_add(1));
+        Self::answer_requests(&who, feed, price);
+
+        T::OracleMixedIn::on_witnessed(&who, T::ServiceId::get(), feed);
+        Self::deposit_event(RawEvent::PriceReported(who, feed, price));
+        Ok(())
+    }
+
+    /// The current reporting round.
+    pub fn current_round() -> Round {
+        let block_number = <system::Module<T>>::block_number();
+        (block_number / T::RoundLength::get()).saturated_into::<Round>() + 1
+    }
+
+    /// Predicate if an oracle can report a feed in the current round, as checked by `report`.
+    pub fn can_report(feed: FeedId, oracle: &T::AccountId) -> bool {
+        Self::feeds().contains(&feed)
+            && Self::feed_mode(feed) == SubmissionMode::Reports
+            && Self::last_round(&(feed, oracle.clone())) < Self::current_round()
+    }
+
+    /// The count of blocks until the current round ends, including the current one.
+    fn blocks_left_in_round() -> T::BlockNumber {
+        let block_number = <system::Module<T>>::block_number();
+        T::RoundLength::get() - block_number % T::RoundLength::get()
+    }
+
+    /// Check a report of `round` is allowed after one of `last` round: only the current round
+    /// is, so older signed attestations can't be replayed, and only once.
+    fn check_round(last: Round, round: Round) -> Result {
+        let current_round = Self::current_round();
+        if round > current_round {
+            return Err("report is too early");
+        }
+        if round < current_round {
+            return Err("attestation round is outdated");
+        }
+        if round <= last {
+            return Err("price already reported in this round");
+        }
         Ok(())
     }
 
+    /// Verify a signed attestation of a feed in reports mode, returning the oracle it reports for.
+    fn verify_attestation(
+        signed: &SignedAttestation<T::AccountId, T::Signature>,
+        oracles: &[T::AccountId],
+    ) -> rstd::result::Result<T::AccountId, &'static str> {
+        let attestation = &signed.attestation;
+        // Attestations can be signed by an oracle key on behalf of its owner.
+        let oracle = T::OracleMixedIn::key_owner(&signed.signer)
+            .unwrap_or_else(|| signed.signer.clone());
+        if !oracles.contains(&oracle) {
+            return Err("attestation is not signed by an oracle");
+        }
+        if !Self::feeds().contains(&attestation.feed) {
+            return Err("unknown feed");
+        }
+        if Self::feed_mode(attestation.feed) != SubmissionMode::Reports {
+            return Err("feed only accepts aggregate signatures");
+        }
+        if !signed.signature.verify(&attestation.signing_payload()[..], &signed.signer) {
+            return Err("bad attestation signature");
+        }
+        Ok(oracle)
+    }
+
+    /// Reports also answer open requests of the same feed.
+    fn answer_requests(who: &T::AccountId, feed: FeedId, price: Price) {
+        Self::open_requests().into_iter().for_each(|id| {
+            <Requests<T>>::mutate(id, |request| {
+                if let Some(request) = request {
+                    if request.feed == feed {
+                        insert_report(&mut request.answers, who, price);
+                    }
+                }
+            });
+        });
+    }
+
+}
+
+impl<T: Trait> support::unsigned::ValidateUnsigned for Module<T> {
+    type Call = Call<T>;
+
+    fn validate_unsigned(call: &Self::Call) -> TransactionValidity {
+        if let Call::report_unsigned(signed) = call {
+            let oracle = match Self::verify_attestation(signed, &T::OracleMixedIn::oracles()) {
+                Ok(oracle) => oracle,
+                Err(_) => return TransactionValidity::Invalid(ApplyError::BadSignature as i8),
+            };
+
+            let attestation = &signed.attestation;
+            let key = (attestation.feed, oracle);
+            let current_round = Self::current_round();
+            if attestation.round > current_round {
+                return TransactionValidity::Invalid(ApplyError::Future as i8);
+            }
+            if attestation.round < current_round || attestation.round <= Self::last_round(&key) {
+                return TransactionValidity::Invalid(ApplyError::Stale as i8);
+            }
+
+            // One report per oracle per round of a feed, dropped once the round ends.
+            let round_left = Self::blocks_left_in_round().saturated_into::<TransactionLongevity>();
+            return TransactionValidity::Valid(ValidTransaction {
+                priority: T::UnsignedPriority::get(),
+                requires: vec![],
+                provides: vec![(key, attestation.round).encode()],
+                longevity: rstd::cmp::min(T::UnsignedLongevity::get(), round_left),
+                propagate: true,
+            });
+        }
+        TransactionValidity::Invalid(0)
+    }
+}
+
+impl<T: Trait> OracleDuties for Module<T> {
+    fn mandatory_duties() -> Vec<(ServiceId, DutyId)> {
+        Self::feeds()
+            .into_iter()
+            .map(|feed| (T::ServiceId::get(), feed))
+            .collect()
+    }
+}
+
+/// The count of `oracles` reaching `threshold`, rounded up and at least one.
+pub fn required_signers(threshold: Perbill, oracles: usize) -> usize {
+    let parts = threshold.deconstruct() as u64 * oracles as u64;
+    let required = (parts + 999_999_999) / 1_000_000_000;
+    rstd::cmp::max(required as usize, 1)
+}
+
+/// Predicate if `new` moved away from `old` by more than `deviation` basis points.
+fn exceeds_deviation(old: Price, new: Price, deviation: u32) -> bool {
+    if old == new {
+        return false;
+    }
+    if old == 0 {
+        return true;
+    }
+
+    let diff = if new > old { new - old } else { old - new };
+    diff.saturating_mul(10_000) > old.saturating_mul(deviation as Price)
+}
+
+/// Deviation of `new` from `old`, in basis points.
+fn deviation(old: Price, new: Price) -> u32 {
+    if old == new {
+        return 0;
+    }
+    if old == 0 {
+        return u32::max_value();
+    }
+
+    let diff = if new > old { new - old } else { old - new };
+    let deviation = diff.saturating_mul(10_000) / old;
+    if deviation > u32::max_value() as Price {
+        u32::max_value()
+    } else {
+        deviation as u32
+    }
+}
+
+/// Accuracy of a report deviating `deviation` basis points, i.e. its inverse deviation.
+fn accuracy(deviation: u32) -> Perbill {
+    Perbill::from_rational_approximation(10_000u64, 10_000u64 + deviation as u64)
+}
+
+/// Insert a report, replacing the previous one from the same reporter.
+fn insert_report<AccountId: PartialEq + Clone>(
+    reports: &mut Vec<PriceReport<AccountId>>,
+    who: &AccountId,
+    price: Price,
+) {
+    match reports.iter_mut().find(|x| &x.reporter == who) {
+        Some(report) => report.price = price,
+        None => reports.push(PriceReport {
+            reporter: who.clone(),
+            price: price,
+        }),
+    }
 }
 
 fn mean(numbers: &Vec<Price>) -> Price {
@@ -123,8 +912,35 @@ decl_event!(
     pub enum Event<T>
     where
         AccountId = <T as system::Trait>::AccountId,
+        Balance = BalanceOf<T>,
     {
-        PriceReported(AccountId, Price),
-        PriceChanged(Price),
+        PriceReported(AccountId, FeedId, Price),
+        PriceChanged(FeedId, Price),
+
+        /// Price of a feed requested by one account, with its fee.
+        PriceRequested(RequestId, AccountId, FeedId, Balance),
+        /// Price request fulfilled with the aggregated price.
+        PriceRequestFulfilled(RequestId, Price),
+        /// Price request unanswered, fee refunded.
+        PriceRequestUnanswered(RequestId),
+        /// Price request cancelled as its feed was removed, fee refunded.
+        PriceRequestCancelled(RequestId),
+
+        /// Price feed added.
+        FeedAdded(FeedId),
+        /// Price feed removed.
+        FeedRemoved(FeedId),
+        /// Update policy of a price feed set.
+        UpdatePolicySet(FeedId),
+        /// Price feed frozen by the circuit breaker, with committed and rejected prices.
+        FeedFrozen(FeedId, Price, Price),
+        /// Price feed unfrozen.
+        FeedUnfrozen(FeedId),
+        /// Bundle of attestations submitted by one account, with its size.
+        AggregateSubmitted(AccountId, u32),
+        /// Price of a feed for a round submitted with an aggregate signature of a threshold of oracles.
+        ThresholdSubmitted(FeedId, Round, Price),
+        /// Submission mode of a price feed set.
+        FeedModeSet(FeedId, SubmissionMode),
     }
 );
@@ -0,0 +1,64 @@
+//! Storage migrations of the price module, run once in `on_initialize` after a runtime upgrade.
+
+use rstd::prelude::*;
+use support::traits::Get;
+use support::{StorageMap, StorageValue};
+
+use crate::{CurrentPrice, FeedPrice, Feeds, Module, PriceReports, StorageVersion, Trait};
+
+/// Version of the current storage layout.
+pub const STORAGE_VERSION: u32 = 1;
+
+/// Storage items of version 0 that have changed since.
+pub mod v0 {
+    use crate::{Price, PriceReport, Trait};
+    use rstd::marker::PhantomData;
+    use rstd::prelude::*;
+    use support::decl_storage;
+
+    pub struct Module<T: Trait>(PhantomData<T>);
+
+    decl_storage! {
+        trait Store for Module<T: Trait> as PriceStorate {
+            pub CurrentPrice: Option<Price>;
+            pub PriceReports: Option<Vec<PriceReport<T::AccountId>>>;
+        }
+    }
+}
+
+/// Migrate the storage from its stored version to `STORAGE_VERSION`.
+pub fn migrate<T: Trait>() {
+    let version = <Module<T>>::storage_version();
+    if version < 1 {
+        migrate_to_v1::<T>();
+    }
+    <StorageVersion>::put(STORAGE_VERSION);
+}
+
+/// Version 0 kept a single price and its reports, they become those of `LegacyFeed`.
+fn migrate_to_v1<T: Trait>() {
+    let feed = T::LegacyFeed::get();
+    let price = <v0::CurrentPrice>::take();
+    let reports = <v0::PriceReports<T>>::take();
+    if price.is_none() && reports.is_none() {
+        return;
+    }
+
+    if let Some(price) = price {
+        if !<CurrentPrice<T>>::exists(feed) {
+            <CurrentPrice<T>>::insert(feed, FeedPrice {
+                price: price,
+                updated_at: <system::Module<T>>::block_number(),
+                timestamp: <timestamp::Module<T>>::now(),
+            });
+        }
+    }
+    if let Some(reports) = reports {
+        <PriceReports<T>>::insert(feed, reports);
+    }
+    Feeds::mutate(|feeds| {
+        if !feeds.contains(&feed) {
+            feeds.push(feed);
+        }
+    });
+}
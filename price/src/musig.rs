@@ -0,0 +1,41 @@
+//! Aggregation of sr25519 signatures of oracles by MuSig, verified in the runtime.
+
+use primitives::H512;
+use rstd::prelude::*;
+use schnorrkel::musig::{aggregate_public_key_from_slice, AggregatePublicKey};
+use schnorrkel::{signing_context, PublicKey, Signature};
+
+use crate::SignatureAggregation;
+
+/// Signing context of MuSig sessions over attestations.
+pub const MUSIG_CONTEXT: &[u8] = b"price-musig";
+
+/// MuSig over sr25519 keys. Signers run the MuSig rounds off chain over the attestation's
+/// signing payload in `MUSIG_CONTEXT`, and their one signature verifies against the aggregate
+/// of their public keys.
+pub struct Sr25519MuSig;
+
+impl<AccountId: AsRef<[u8]>> SignatureAggregation<AccountId> for Sr25519MuSig {
+    type Signature = H512;
+
+    fn verify(keys: &[AccountId], payload: &[u8], signature: &H512) -> bool {
+        let mut public_keys = Vec::with_capacity(keys.len());
+        for key in keys {
+            match PublicKey::from_bytes(key.as_ref()) {
+                Ok(public) => public_keys.push(public),
+                Err(_) => return false,
+            }
+        }
+        let aggregate = match aggregate_public_key_from_slice(&mut public_keys) {
+            Some(aggregate) => aggregate.public_key(),
+            None => return false,
+        };
+
+        match Signature::from_bytes(signature.as_bytes()) {
+            Ok(signature) => aggregate
+                .verify(signing_context(MUSIG_CONTEXT).bytes(payload), &signature)
+                .is_ok(),
+            Err(_) => false,
+        }
+    }
+}
@@ -0,0 +1,650 @@
+/// tests for this module
+#[cfg(test)]
+mod tests {
+	use super::super::*;
+	use crate::migration::{self, v0};
+
+	use std::cell::RefCell;
+	use runtime_io::with_externalities;
+	use primitives::{H256, Blake2Hasher};
+	use support::{impl_outer_origin, assert_noop, assert_ok, parameter_types};
+	use support::unsigned::ValidateUnsigned;
+	use sr_primitives::{traits::{BlakeTwo256, ConvertInto, IdentityLookup, Lazy, OnFinalize, OnInitialize}, testing::Header};
+	use sr_primitives::weights::Weight;
+
+	impl_outer_origin! {
+		pub enum Origin for Test {}
+	}
+
+	// For testing the module, we construct most of a mock runtime. This means
+	// first constructing a configuration type (`Test`) which `impl`s each of the
+	// configuration traits of modules we want to use.
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
+	}
+	impl system::Trait for Test {
+		type Origin = Origin;
+		type Call = ();
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type WeightMultiplierUpdate = ();
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+	}
+	parameter_types! {
+		pub const MinimumPeriod: u64 = 1;
+	}
+	impl timestamp::Trait for Test {
+		type Moment = u64;
+		type OnTimestampSet = ();
+		type MinimumPeriod = MinimumPeriod;
+	}
+	parameter_types! {
+		pub const ExistentialDeposit: u64 = 0;
+		pub const TransferFee: u64 = 0;
+		pub const CreationFee: u64 = 0;
+		pub const TransactionBaseFee: u64 = 0;
+		pub const TransactionByteFee: u64 = 0;
+	}
+	impl balances::Trait for Test {
+		type Balance = u64;
+		type OnFreeBalanceZero = ();
+		type OnNewAccount = ();
+		type Event = ();
+		type TransactionPayment = ();
+		type DustRemoval = ();
+		type TransferPayment = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type TransferFee = TransferFee;
+		type CreationFee = CreationFee;
+		type TransactionBaseFee = TransactionBaseFee;
+		type TransactionByteFee = TransactionByteFee;
+		type WeightToFee = ConvertInto;
+	}
+
+	/// Acting oracles are set by each test, none by default. Reports only need a signed origin,
+	/// and oracles sign with their own account.
+	pub struct TestOracles;
+	thread_local! {
+		static ORACLES: RefCell<Vec<u64>> = RefCell::new(vec![]);
+		static ADDED_DUTIES: RefCell<Vec<(ServiceId, DutyId)>> = RefCell::new(vec![]);
+		static DEVIATIONS: RefCell<Vec<(u64, u32)>> = RefCell::new(vec![]);
+		static MISSES: RefCell<Vec<(u64, DutyId)>> = RefCell::new(vec![]);
+	}
+	impl OracleMixedIn<Test> for TestOracles {
+		fn on_witnessed(_: &u64, _: ServiceId, _: DutyId) {}
+		fn on_duty_added(service: ServiceId, duty: DutyId) {
+			ADDED_DUTIES.with(|d| d.borrow_mut().push((service, duty)));
+		}
+		fn is_valid(_: &u64, _: ServiceId, _: DutyId) -> bool { true }
+		fn on_missed(who: &u64, _: ServiceId, duty: DutyId) {
+			MISSES.with(|m| m.borrow_mut().push((*who, duty)));
+		}
+		fn on_deviation(who: &u64, _: ServiceId, _: DutyId, deviation: u32) {
+			DEVIATIONS.with(|d| d.borrow_mut().push((*who, deviation)));
+		}
+		fn on_accuracy(_: &u64, _: ServiceId, _: DutyId, _: Perbill) {}
+		fn oracles() -> Vec<u64> { ORACLES.with(|o| o.borrow().clone()) }
+		fn key_owner(_: &u64) -> Option<u64> { None }
+		fn oracle_key(who: &u64) -> u64 { *who }
+	}
+
+	/// Signature valid for its own signer only.
+	#[derive(Clone, Eq, PartialEq, Debug, Encode, Decode)]
+	pub struct TestSignature(u64);
+	impl Verify for TestSignature {
+		type Signer = u64;
+		fn verify<L: Lazy<[u8]>>(&self, _: L, signer: &u64) -> bool {
+			self.0 == *signer
+		}
+	}
+
+	/// Aggregate signature valid for exactly the keys it lists.
+	pub struct TestAggregation;
+	impl SignatureAggregation<u64> for TestAggregation {
+		type Signature = Vec<u64>;
+		fn verify(keys: &[u64], _: &[u8], signature: &Vec<u64>) -> bool {
+			keys == &signature[..]
+		}
+	}
+
+	parameter_types! {
+		pub const PriceServiceId: ServiceId = 0;
+		pub const RequestPeriod: u64 = 10;
+		pub const MinAnswers: u32 = 2;
+		pub const MinRequestFee: u64 = 10;
+		pub const MaxOpenRequests: u32 = 2;
+		pub const DefaultDeviation: u32 = 50;
+		pub const DefaultHeartbeat: u64 = 100;
+		pub const DefaultCircuitBreaker: u32 = 0;
+		pub const SignatureThreshold: Perbill = Perbill::from_percent(67);
+		pub const UnsignedPriority: TransactionPriority = 1;
+		pub const UnsignedLongevity: TransactionLongevity = 10;
+		pub const RoundLength: u64 = 5;
+		pub const LegacyFeed: FeedId = 7;
+	}
+	impl Trait for Test {
+		type Event = ();
+		type OracleMixedIn = TestOracles;
+		type ServiceId = PriceServiceId;
+		type ReportOrigin = system::EnsureSigned<u64>;
+		type Currency = Balances;
+		type RequestPeriod = RequestPeriod;
+		type MinAnswers = MinAnswers;
+		type MinRequestFee = MinRequestFee;
+		type MaxOpenRequests = MaxOpenRequests;
+		type PriceConsumer = ();
+		type DefaultDeviation = DefaultDeviation;
+		type DefaultHeartbeat = DefaultHeartbeat;
+		type DefaultCircuitBreaker = DefaultCircuitBreaker;
+		type UnfreezeOrigin = system::EnsureRoot<u64>;
+		type Signature = TestSignature;
+		type SignatureThreshold = SignatureThreshold;
+		type SignatureAggregation = TestAggregation;
+		type UnsignedPriority = UnsignedPriority;
+		type UnsignedLongevity = UnsignedLongevity;
+		type RoundLength = RoundLength;
+		type LegacyFeed = LegacyFeed;
+	}
+	type Balances = balances::Module<Test>;
+	type PriceModule = Module<Test>;
+
+	// This function basically just builds a genesis storage key/value store according to
+	// our desired mockup.
+	fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+		let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		balances::GenesisConfig::<Test> {
+			balances: vec![(1, 100), (2, 100), (3, 100), (10, 1000)],
+			vesting: vec![],
+		}.assimilate_storage(&mut t).unwrap();
+		GenesisConfig {
+			feeds: vec![0],
+		}.assimilate_storage::<Test>(&mut t).unwrap();
+		t.into()
+	}
+
+	fn report(reporter: u64, price: Price) -> PriceReport<u64> {
+		PriceReport { reporter, price }
+	}
+
+	fn set_oracles(oracles: Vec<u64>) {
+		ORACLES.with(|o| *o.borrow_mut() = oracles);
+	}
+
+	fn signed(signer: u64, price: Price, round: Round) -> SignedAttestation<u64, TestSignature> {
+		SignedAttestation {
+			attestation: Attestation { feed: 0, price, round },
+			signer,
+			signature: TestSignature(signer),
+		}
+	}
+
+	fn finalize(block: u64) {
+		system::Module::<Test>::set_block_number(block);
+		PriceModule::on_finalize(block);
+	}
+
+	#[test]
+	fn genesis_is_at_current_storage_version() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_eq!(PriceModule::storage_version(), migration::STORAGE_VERSION);
+		});
+	}
+
+	#[test]
+	fn migrates_v0_price_to_legacy_feed() {
+		with_externalities(&mut new_test_ext(), || {
+			system::Module::<Test>::set_block_number(5);
+			<StorageVersion>::put(0u32);
+			<v0::CurrentPrice>::put(42 as Price);
+			<v0::PriceReports<Test>>::put(vec![report(1, 40), report(2, 44)]);
+
+			PriceModule::on_initialize(5);
+
+			assert_eq!(PriceModule::storage_version(), migration::STORAGE_VERSION);
+			assert_eq!(PriceModule::feeds(), vec![0, 7]);
+			assert_eq!(PriceModule::current_price(7), Some(FeedPrice {
+				price: 42,
+				updated_at: 5,
+				timestamp: 0,
+			}));
+			assert_eq!(PriceModule::price_reports(7), vec![report(1, 40), report(2, 44)]);
+			assert!(!<v0::CurrentPrice>::exists());
+			assert!(!<v0::PriceReports<Test>>::exists());
+		});
+	}
+
+	#[test]
+	fn migration_without_v0_price_keeps_feeds() {
+		with_externalities(&mut new_test_ext(), || {
+			<StorageVersion>::put(0u32);
+
+			PriceModule::on_initialize(1);
+
+			assert_eq!(PriceModule::storage_version(), migration::STORAGE_VERSION);
+			assert_eq!(PriceModule::feeds(), vec![0]);
+			assert_eq!(PriceModule::current_price(7), None);
+		});
+	}
+
+	#[test]
+	fn added_feeds_become_duties_of_acting_oracles() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_noop!(PriceModule::add_feed(Origin::signed(1), 1), "bad origin: expected to be a root origin");
+			assert_noop!(PriceModule::add_feed(Origin::ROOT, 0), "feed already exists");
+			assert_ok!(PriceModule::add_feed(Origin::ROOT, 1));
+
+			assert_eq!(PriceModule::feeds(), vec![0, 1]);
+			assert_eq!(PriceModule::mandatory_duties(), vec![(0, 0), (0, 1)]);
+			assert_eq!(ADDED_DUTIES.with(|d| d.borrow().clone()), vec![(0, 1)]);
+		});
+	}
+
+	#[test]
+	fn removed_feeds_are_cleared() {
+		with_externalities(&mut new_test_ext(), || {
+			set_oracles(vec![1, 2]);
+			assert_ok!(PriceModule::request_price(Origin::signed(10), 0, 5, 50));
+			assert_ok!(PriceModule::report(Origin::signed(1), 0, 100));
+			finalize(0);
+			assert!(PriceModule::current_price(0).is_some());
+			assert_eq!(PriceModule::open_requests(), vec![0]);
+			assert_eq!(Balances::reserved_balance(&10), 50);
+
+			assert_noop!(PriceModule::remove_feed(Origin::ROOT, 1), "unknown feed");
+			assert_ok!(PriceModule::remove_feed(Origin::ROOT, 0));
+			assert_eq!(PriceModule::feeds(), vec![]);
+			assert_eq!(PriceModule::current_price(0), None);
+			assert_eq!(PriceModule::price_reports(0), vec![]);
+			assert_eq!(PriceModule::open_requests(), vec![]);
+			assert_eq!(PriceModule::request(0), None);
+			assert_eq!(Balances::reserved_balance(&10), 0);
+			assert_eq!(Balances::free_balance(&10), 1000);
+			finalize(11);
+			assert_eq!(MISSES.with(|m| m.borrow().clone()), vec![]);
+			assert_noop!(PriceModule::report(Origin::signed(1), 0, 100), "unknown feed");
+			assert_noop!(PriceModule::request_price(Origin::signed(10), 0, 5, 50), "unknown feed");
+		});
+	}
+
+	#[test]
+	fn requests_of_fresh_prices_are_answered_at_once() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(PriceModule::report(Origin::signed(1), 0, 100));
+			finalize(1);
+
+			system::Module::<Test>::set_block_number(3);
+			assert_ok!(PriceModule::request_price(Origin::signed(10), 0, 5, 50));
+			assert_eq!(PriceModule::next_request_id(), 1);
+			assert_eq!(PriceModule::request(0), None);
+			assert_eq!(PriceModule::open_requests(), vec![]);
+			assert_eq!(Balances::reserved_balance(&10), 0);
+		});
+	}
+
+	#[test]
+	fn requests_are_paid_to_answering_oracles() {
+		with_externalities(&mut new_test_ext(), || {
+			system::Module::<Test>::set_block_number(1);
+			assert_ok!(PriceModule::request_price(Origin::signed(10), 0, 5, 50));
+			assert_eq!(Balances::reserved_balance(&10), 50);
+			assert_eq!(PriceModule::open_requests(), vec![0]);
+
+			assert_ok!(PriceModule::report(Origin::signed(1), 0, 100));
+			finalize(1);
+			assert_eq!(PriceModule::open_requests(), vec![0]);
+
+			assert_ok!(PriceModule::report(Origin::signed(2), 0, 110));
+			finalize(2);
+			assert_eq!(PriceModule::request(0), None);
+			assert_eq!(PriceModule::open_requests(), vec![]);
+			assert_eq!(Balances::free_balance(&1), 125);
+			assert_eq!(Balances::free_balance(&2), 125);
+			assert_eq!(Balances::reserved_balance(&10), 0);
+			assert_eq!(Balances::free_balance(&10), 950);
+		});
+	}
+
+	#[test]
+	fn requests_need_a_fee_and_are_capped() {
+		with_externalities(&mut new_test_ext(), || {
+			system::Module::<Test>::set_block_number(1);
+			assert_noop!(PriceModule::request_price(Origin::signed(10), 0, 5, 5), "request fee is too small");
+			assert_ok!(PriceModule::request_price(Origin::signed(10), 0, 5, 10));
+			assert_ok!(PriceModule::request_price(Origin::signed(10), 0, 5, 10));
+			assert_noop!(PriceModule::request_price(Origin::signed(10), 0, 5, 10), "too many open requests");
+			assert_eq!(PriceModule::next_request_id(), 2);
+			assert_eq!(Balances::reserved_balance(&10), 20);
+
+			// Fresh prices are still answered at once, for free.
+			assert_ok!(PriceModule::report(Origin::signed(1), 0, 100));
+			finalize(1);
+			assert_ok!(PriceModule::request_price(Origin::signed(10), 0, 5, 0));
+			assert_eq!(PriceModule::next_request_id(), 3);
+		});
+	}
+
+	#[test]
+	fn unanswered_requests_are_refunded() {
+		with_externalities(&mut new_test_ext(), || {
+			system::Module::<Test>::set_block_number(1);
+			assert_ok!(PriceModule::request_price(Origin::signed(10), 0, 5, 50));
+			assert_ok!(PriceModule::report(Origin::signed(1), 0, 100));
+
+			finalize(11);
+			assert_eq!(PriceModule::open_requests(), vec![0]);
+			finalize(12);
+			assert_eq!(PriceModule::request(0), None);
+			assert_eq!(PriceModule::open_requests(), vec![]);
+			assert_eq!(Balances::free_balance(&1), 100);
+			assert_eq!(Balances::reserved_balance(&10), 0);
+			assert_eq!(Balances::free_balance(&10), 1000);
+		});
+	}
+
+	fn report_at(block: u64, reporter: u64, price: Price) {
+		system::Module::<Test>::set_block_number(block);
+		assert_ok!(PriceModule::report(Origin::signed(reporter), 0, price));
+		PriceModule::on_finalize(block);
+	}
+
+	fn committed_price() -> (Price, u64) {
+		let current = PriceModule::current_price(0).unwrap();
+		(current.price, current.updated_at)
+	}
+
+	#[test]
+	fn prices_are_committed_by_deviation_or_heartbeat() {
+		with_externalities(&mut new_test_ext(), || {
+			report_at(0, 1, 10_000);
+			assert_eq!(committed_price(), (10_000, 0));
+
+			// Within the default deviation of 50 basis points.
+			report_at(5, 1, 10_040);
+			assert_eq!(committed_price(), (10_000, 0));
+			report_at(10, 1, 10_060);
+			assert_eq!(committed_price(), (10_060, 10));
+
+			let policy = UpdatePolicy { deviation: 1_000, heartbeat: 20, circuit_breaker: 0 };
+			assert_noop!(
+				PriceModule::set_update_policy(Origin::signed(1), 0, policy.clone()),
+				"bad origin: expected to be a root origin"
+			);
+			assert_noop!(PriceModule::set_update_policy(Origin::ROOT, 1, policy.clone()), "unknown feed");
+			assert_ok!(PriceModule::set_update_policy(Origin::ROOT, 0, policy));
+
+			report_at(15, 1, 10_500);
+			assert_eq!(committed_price(), (10_060, 10));
+			report_at(30, 1, 10_500);
+			assert_eq!(committed_price(), (10_500, 30));
+		});
+	}
+
+	#[test]
+	fn circuit_breaker_freezes_feed_until_unfrozen() {
+		with_externalities(&mut new_test_ext(), || {
+			let policy = UpdatePolicy { deviation: 50, heartbeat: 0, circuit_breaker: 1_000 };
+			assert_ok!(PriceModule::set_update_policy(Origin::ROOT, 0, policy));
+			report_at(0, 1, 10_000);
+
+			report_at(5, 1, 12_000);
+			assert!(PriceModule::is_frozen(0));
+			assert_eq!(PriceModule::frozen_feeds(0), Some(5));
+			assert_eq!(committed_price(), (10_000, 0));
+			assert_noop!(PriceModule::request_price(Origin::signed(10), 0, 5, 50), "feed is frozen");
+
+			report_at(10, 1, 12_100);
+			assert_eq!(committed_price(), (10_000, 0));
+
+			assert_noop!(PriceModule::unfreeze_feed(Origin::signed(1), 0), "bad origin");
+			assert_ok!(PriceModule::unfreeze_feed(Origin::ROOT, 0));
+			assert!(!PriceModule::is_frozen(0));
+			assert_eq!(committed_price(), (12_100, 10));
+			assert_noop!(PriceModule::unfreeze_feed(Origin::ROOT, 0), "feed is not frozen");
+		});
+	}
+
+	#[test]
+	fn aggregates_signed_attestations_in_one_transaction() {
+		with_externalities(&mut new_test_ext(), || {
+			set_oracles(vec![1, 2]);
+			system::Module::<Test>::set_block_number(5);
+
+			assert_ok!(PriceModule::submit_aggregate(Origin::signed(10), vec![signed(1, 100, 2), signed(2, 110, 2)]));
+			assert_eq!(PriceModule::price_reports(0), vec![report(1, 100), report(2, 110)]);
+			assert_eq!(PriceModule::last_round(&(0, 1)), 2);
+			assert_eq!(PriceModule::last_round(&(0, 2)), 2);
+			assert_eq!(PriceModule::submission_count(1), 1);
+		});
+	}
+
+	#[test]
+	fn rejects_whole_bundle_with_an_invalid_attestation() {
+		with_externalities(&mut new_test_ext(), || {
+			set_oracles(vec![1, 2]);
+			system::Module::<Test>::set_block_number(5);
+			let valid = signed(1, 100, 2);
+
+			assert_noop!(PriceModule::submit_aggregate(Origin::signed(10), vec![]), "empty bundle");
+			assert_noop!(
+				PriceModule::submit_aggregate(Origin::signed(10), vec![valid.clone(), signed(3, 100, 2)]),
+				"attestation is not signed by an oracle"
+			);
+			let mut forged = signed(2, 100, 2);
+			forged.signature = TestSignature(1);
+			assert_noop!(
+				PriceModule::submit_aggregate(Origin::signed(10), vec![valid.clone(), forged]),
+				"bad attestation signature"
+			);
+			assert_noop!(
+				PriceModule::submit_aggregate(Origin::signed(10), vec![valid.clone(), signed(1, 110, 2)]),
+				"price already reported in this round"
+			);
+			assert_noop!(
+				PriceModule::submit_aggregate(Origin::signed(10), vec![valid, signed(2, 100, 3)]),
+				"report is too early"
+			);
+			assert_eq!(PriceModule::price_reports(0), vec![]);
+		});
+	}
+
+	#[test]
+	fn required_signers_are_rounded_up() {
+		assert_eq!(required_signers(Perbill::from_percent(67), 3), 3);
+		assert_eq!(required_signers(Perbill::from_parts(666_666_666), 3), 2);
+		assert_eq!(required_signers(Perbill::from_percent(67), 100), 67);
+		assert_eq!(required_signers(Perbill::from_percent(0), 5), 1);
+	}
+
+	#[test]
+	fn aggregate_signature_of_threshold_commits_price() {
+		with_externalities(&mut new_test_ext(), || {
+			set_oracles(vec![1, 2, 3]);
+			system::Module::<Test>::set_block_number(5);
+			let attestation = Attestation { feed: 0, price: 100, round: 2 };
+			let aggregate = |signers: Vec<u32>| AggregateSignature {
+				signature: signers.iter().map(|i| *i as u64 + 1).collect(),
+				signers,
+			};
+
+			assert_noop!(
+				PriceModule::submit_threshold(Origin::signed(10), attestation.clone(), aggregate(vec![0, 1, 2])),
+				"feed doesn't accept aggregate signatures"
+			);
+			assert_ok!(PriceModule::set_feed_mode(Origin::ROOT, 0, SubmissionMode::Threshold));
+			assert_noop!(PriceModule::report(Origin::signed(1), 0, 100), "feed only accepts aggregate signatures");
+
+			// 67% of 3 oracles is rounded up to all of them.
+			assert_noop!(
+				PriceModule::submit_threshold(Origin::signed(10), attestation.clone(), aggregate(vec![0, 1])),
+				"not enough signatures"
+			);
+			assert_noop!(
+				PriceModule::submit_threshold(Origin::signed(10), attestation.clone(), AggregateSignature {
+					signers: vec![0, 1, 2],
+					signature: vec![1, 2],
+				}),
+				"bad aggregate signature"
+			);
+			assert_noop!(
+				PriceModule::submit_threshold(Origin::signed(10), attestation.clone(), aggregate(vec![0, 1, 3])),
+				"signer is not an oracle"
+			);
+			assert_ok!(PriceModule::submit_threshold(Origin::signed(10), attestation.clone(), aggregate(vec![0, 1, 2])));
+			assert_eq!(committed_price(), (100, 5));
+			assert_eq!(PriceModule::last_agreed_round(0), 2);
+			assert_noop!(
+				PriceModule::submit_threshold(Origin::signed(10), attestation, aggregate(vec![0, 1, 2])),
+				"price already reported in this round"
+			);
+		});
+	}
+
+	#[test]
+	fn old_aggregate_signatures_cannot_be_replayed() {
+		with_externalities(&mut new_test_ext(), || {
+			set_oracles(vec![1, 2, 3]);
+			assert_ok!(PriceModule::set_feed_mode(Origin::ROOT, 0, SubmissionMode::Threshold));
+			let signature = || AggregateSignature {
+				signers: vec![0, 1, 2],
+				signature: vec![1, 2, 3],
+			};
+			let submit = |round| {
+				let attestation = Attestation { feed: 0, price: 100, round };
+				PriceModule::submit_threshold(Origin::signed(10), attestation, signature())
+			};
+
+			// Signed in round 2, nobody submitted it nor a later one.
+			system::Module::<Test>::set_block_number(15);
+			assert_noop!(submit(2), "attestation round is outdated");
+			assert_noop!(submit(5), "report is too early");
+			assert_ok!(submit(4));
+			assert_eq!(PriceModule::last_agreed_round(0), 4);
+		});
+	}
+
+	#[test]
+	fn can_report_once_per_round() {
+		with_externalities(&mut new_test_ext(), || {
+			system::Module::<Test>::set_block_number(5);
+			assert!(PriceModule::can_report(0, &1));
+			assert!(!PriceModule::can_report(1, &1));
+
+			assert_ok!(PriceModule::report(Origin::signed(1), 0, 100));
+			assert!(!PriceModule::can_report(0, &1));
+			assert!(PriceModule::can_report(0, &2));
+
+			system::Module::<Test>::set_block_number(10);
+			assert!(PriceModule::can_report(0, &1));
+			assert_ok!(PriceModule::set_feed_mode(Origin::ROOT, 0, SubmissionMode::Threshold));
+			assert!(!PriceModule::can_report(0, &1));
+		});
+	}
+
+	#[test]
+	fn reports_once_per_round() {
+		with_externalities(&mut new_test_ext(), || {
+			system::Module::<Test>::set_block_number(5);
+			assert_ok!(PriceModule::report(Origin::signed(1), 0, 100));
+			assert_noop!(PriceModule::report(Origin::signed(1), 0, 101), "price already reported in this round");
+			assert_eq!(PriceModule::last_round(&(0, 1)), 2);
+
+			system::Module::<Test>::set_block_number(10);
+			assert_ok!(PriceModule::report(Origin::signed(1), 0, 102));
+			assert_eq!(PriceModule::last_round(&(0, 1)), 3);
+			assert_eq!(PriceModule::submission_count(1), 2);
+			assert_eq!(PriceModule::price_reports(0), vec![report(1, 102)]);
+		});
+	}
+
+	#[test]
+	fn unsigned_reports_are_valid_once_until_round_ends() {
+		with_externalities(&mut new_test_ext(), || {
+			set_oracles(vec![1]);
+			system::Module::<Test>::set_block_number(7);
+			let call = Call::report_unsigned(signed(1, 100, 2));
+
+			assert_eq!(PriceModule::validate_unsigned(&call), TransactionValidity::Valid(ValidTransaction {
+				priority: 1,
+				requires: vec![],
+				provides: vec![((0 as FeedId, 1u64), 2 as Round).encode()],
+				longevity: 3,
+				propagate: true,
+			}));
+			assert_eq!(
+				PriceModule::validate_unsigned(&Call::report_unsigned(signed(1, 100, 3))),
+				TransactionValidity::Invalid(ApplyError::Future as i8)
+			);
+			assert_eq!(
+				PriceModule::validate_unsigned(&Call::report_unsigned(signed(2, 100, 2))),
+				TransactionValidity::Invalid(ApplyError::BadSignature as i8)
+			);
+
+			assert_noop!(PriceModule::report_unsigned(Origin::signed(1), signed(1, 100, 2)), "bad origin: expected to be no origin");
+			assert_ok!(PriceModule::report_unsigned(Origin::NONE, signed(1, 100, 2)));
+			assert_eq!(PriceModule::price_reports(0), vec![report(1, 100)]);
+			assert_eq!(PriceModule::validate_unsigned(&call), TransactionValidity::Invalid(ApplyError::Stale as i8));
+			assert_noop!(
+				PriceModule::report_unsigned(Origin::NONE, signed(1, 101, 2)),
+				"price already reported in this round"
+			);
+		});
+	}
+
+	#[test]
+	fn old_attestations_cannot_be_replayed() {
+		with_externalities(&mut new_test_ext(), || {
+			set_oracles(vec![1]);
+			system::Module::<Test>::set_block_number(10);
+			let old = signed(1, 100, 2);
+
+			assert_noop!(PriceModule::submit_aggregate(Origin::signed(10), vec![old.clone()]), "attestation round is outdated");
+			assert_noop!(PriceModule::report_unsigned(Origin::NONE, old.clone()), "attestation round is outdated");
+			assert_eq!(
+				PriceModule::validate_unsigned(&Call::report_unsigned(old)),
+				TransactionValidity::Invalid(ApplyError::Stale as i8)
+			);
+		});
+	}
+
+	#[test]
+	fn reports_are_scored_when_their_round_closes() {
+		with_externalities(&mut new_test_ext(), || {
+			let deviations = || DEVIATIONS.with(|d| d.replace(vec![]));
+			system::Module::<Test>::set_block_number(5);
+			for &(reporter, price) in &[(1, 100), (2, 110), (3, 120)] {
+				assert_ok!(PriceModule::report(Origin::signed(reporter), 0, price));
+			}
+
+			finalize(5);
+			finalize(8);
+			assert_eq!(deviations(), vec![]);
+			finalize(9);
+			assert_eq!(deviations(), vec![(1, 909), (2, 0), (3, 909)]);
+			assert_eq!(PriceModule::last_scored_round(0), 2);
+			finalize(9);
+			assert_eq!(deviations(), vec![]);
+
+			// Reports of past rounds aren't scored again.
+			system::Module::<Test>::set_block_number(10);
+			assert_ok!(PriceModule::report(Origin::signed(1), 0, 200));
+			finalize(14);
+			assert_eq!(deviations(), vec![(1, 0)]);
+			assert_eq!(PriceModule::last_scored_round(0), 3);
+		});
+	}
+}
@@ -0,0 +1,75 @@
+//! Transaction fees of oracle reports.
+
+use codec::{Decode, Encode};
+use sr_primitives::traits::{DispatchError, SignedExtension};
+use sr_primitives::transaction_validity::ValidTransaction;
+use sr_primitives::weights::DispatchInfo;
+
+use crate::{AccountId, Balance, Call, Oracle, OracleMembers, Price, Runtime};
+
+/// Take transaction fees like `balances::TakeFees`, except for price reports of acting oracles,
+/// which are free. Price reports that would fail, e.g. of any other account or a second one in
+/// the same round, are rejected before entering the pool.
+///
+/// It's encoded as `balances::TakeFees`, so clients keep passing the tip as before.
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct TakeFeesExceptOracles(balances::TakeFees<Runtime>);
+
+impl TakeFeesExceptOracles {
+    /// Utility constructor, used only in client/tests.
+    pub fn from(fee: Balance) -> Self {
+        TakeFeesExceptOracles(balances::TakeFees::from(fee))
+    }
+}
+
+/// The feed of a price report, and whether it's sent through the oracle collective.
+fn price_report(call: &Call) -> Option<(price::FeedId, bool)> {
+    match call {
+        Call::Price(price::Call::report(feed, _)) => Some((*feed, false)),
+        Call::OracleMembers(collective::Call::execute(proposal)) => match proposal.as_ref() {
+            Call::Price(price::Call::report(feed, _)) => Some((*feed, true)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The acting oracle a report sent by `who` is accepted for, as checked by the report origin:
+/// a member of the collective through `execute`, or an oracle key sending it directly.
+fn reporting_oracle(who: &AccountId, through_collective: bool) -> Option<AccountId> {
+    let oracle = if through_collective {
+        Some(who.clone()).filter(|who| OracleMembers::members().contains(who))
+    } else {
+        Oracle::key_owner(who)
+    };
+    oracle.filter(|oracle| Oracle::oracles().contains(oracle))
+}
+
+impl SignedExtension for TakeFeesExceptOracles {
+    type AccountId = AccountId;
+    type Call = Call;
+    type AdditionalSigned = ();
+
+    fn additional_signed(&self) -> rstd::result::Result<(), &'static str> {
+        Ok(())
+    }
+
+    fn validate(
+        &self,
+        who: &Self::AccountId,
+        call: &Self::Call,
+        info: DispatchInfo,
+        len: usize,
+    ) -> rstd::result::Result<ValidTransaction, DispatchError> {
+        let (feed, through_collective) = match price_report(call) {
+            Some(report) => report,
+            None => return self.0.validate(who, call, info, len),
+        };
+
+        match reporting_oracle(who, through_collective) {
+            Some(ref oracle) if Price::can_report(feed, oracle) => Ok(ValidTransaction::default()),
+            _ => Err(DispatchError::NoPermission),
+        }
+    }
+}
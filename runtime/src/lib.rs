@@ -24,17 +24,22 @@ use sr_primitives::traits::{
 };
 use sr_primitives::weights::Weight;
 use sr_primitives::{
-    create_runtime_str, generic, impl_opaque_keys, transaction_validity::TransactionValidity,
+    create_runtime_str, generic, impl_opaque_keys,
+    transaction_validity::{TransactionLongevity, TransactionPriority, TransactionValidity},
     AnySignature, ApplyResult,
 };
 #[cfg(feature = "std")]
 use version::NativeVersion;
 use version::RuntimeVersion;
 
+mod fees;
+
 // A few exports that help ease life for downstream crates.
 pub use balances::Call as BalancesCall;
 pub use oracle;
 pub use price;
+pub use attestation;
+pub use fees::TakeFeesExceptOracles;
 #[cfg(any(feature = "std", test))]
 pub use sr_primitives::BuildStorage;
 pub use sr_primitives::{Perbill, Permill};
@@ -96,6 +101,8 @@ pub mod opaque {
             pub grandpa: GrandpaId,
             #[id(key_types::BABE)]
             pub babe: BabeId,
+            #[id(oracle::ORACLE)]
+            pub oracle: oracle::OracleId,
         }
     }
 }
@@ -105,7 +112,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
     spec_name: create_runtime_str!("node-template"),
     impl_name: create_runtime_str!("node-template"),
     authoring_version: 3,
-    spec_version: 4,
+    spec_version: 5,
     impl_version: 4,
     apis: RUNTIME_API_VERSIONS,
 };
@@ -266,15 +273,77 @@ impl collective::Trait<OracleCollective> for Runtime {
     type Event = Event;
 }
 
+parameter_types! {
+    pub const PriceServiceId: oracle::ServiceId = 0;
+    pub const RequestPeriod: BlockNumber = 10;
+    pub const MinAnswers: u32 = 2;
+    pub const MinRequestFee: Balance = 10 * CENTS;
+    pub const MaxOpenRequests: u32 = 100;
+    pub const DefaultDeviation: u32 = 50;
+    pub const DefaultHeartbeat: BlockNumber = 100;
+    pub const DefaultCircuitBreaker: u32 = 2000;
+    // Two thirds of the oracles, as the count of signers is rounded up.
+    pub const SignatureThreshold: Perbill = Perbill::from_parts(666_666_666);
+    pub const UnsignedPriority: TransactionPriority = TransactionPriority::max_value();
+    pub const UnsignedLongevity: TransactionLongevity = 10;
+    pub const RoundLength: BlockNumber = 5;
+    pub const LegacyFeed: price::FeedId = 0;
+}
+
 impl price::Trait for Runtime {
     type Event = Event;
     type OracleMixedIn = Oracle;
-    type ReportOrigin = collective::EnsureMember<AccountId, OracleCollective>;
+    type ServiceId = PriceServiceId;
+    type ReportOrigin =
+        oracle::EnsureOracle<Runtime, collective::EnsureMember<AccountId, OracleCollective>>;
+    type Currency = Balances;
+    type RequestPeriod = RequestPeriod;
+    type MinAnswers = MinAnswers;
+    type MinRequestFee = MinRequestFee;
+    type MaxOpenRequests = MaxOpenRequests;
+    type PriceConsumer = ();
+    type DefaultDeviation = DefaultDeviation;
+    type DefaultHeartbeat = DefaultHeartbeat;
+    type DefaultCircuitBreaker = DefaultCircuitBreaker;
+    type UnfreezeOrigin =
+        collective::EnsureProportionMoreThan<_1, _2, AccountId, OracleCollective>;
+    type Signature = Signature;
+    type SignatureThreshold = SignatureThreshold;
+    type SignatureAggregation = price::Sr25519MuSig;
+    type UnsignedPriority = UnsignedPriority;
+    type UnsignedLongevity = UnsignedLongevity;
+    type RoundLength = RoundLength;
+    type LegacyFeed = LegacyFeed;
+}
+
+parameter_types! {
+    pub const AttestationServiceId: oracle::ServiceId = 1;
+    pub const AttestationThreshold: u32 = 2;
+    pub const AnswerPeriod: BlockNumber = 50;
+    pub const MaxDataLength: u32 = 1024;
+    pub const MinAttestationBounty: Balance = 10 * CENTS;
+    pub const MaxOpenAttestations: u32 = 100;
+}
+
+impl attestation::Trait for Runtime {
+    type Event = Event;
+    type Currency = Balances;
+    type OracleMixedIn = Oracle;
+    type ServiceId = AttestationServiceId;
+    type ReportOrigin =
+        oracle::EnsureOracle<Runtime, collective::EnsureMember<AccountId, OracleCollective>>;
+    type Threshold = AttestationThreshold;
+    type AnswerPeriod = AnswerPeriod;
+    type MaxDataLength = MaxDataLength;
+    type MinBounty = MinAttestationBounty;
+    type MaxOpenRequests = MaxOpenAttestations;
+    type OnAnswer = ();
 }
 
 parameter_types! {
-    pub const OracleFee: Balance = 1 * DOLLARS;
-    pub const MissReportSlash: Balance = 1 * DOLLARS;
+    pub const MaxSlashPerEra: Balance = 10 * DOLLARS;
+    pub const MaxConsecutiveMisses: u32 = 5;
+    pub const JailCooldown: BlockNumber = 100;
     pub const MinStaking: Balance = 1000 * DOLLARS;
 
     pub const Count: u16 = 3;
@@ -282,6 +351,24 @@ parameter_types! {
     pub const ReportInteval: BlockNumber = 10;
     pub const ElectionEra: BlockNumber = 10;
     pub const LockedDuration: BlockNumber = 1000;
+    pub const SlashDeferDuration: BlockNumber = 100;
+
+    pub const ReputationDecay: Perbill = Perbill::from_percent(10);
+    pub const ReputationWeight: Perbill = Perbill::from_percent(50);
+
+    pub const HistoryDepth: oracle::EraIndex = 84;
+
+    pub const MaxUnlockingChunks: u32 = 32;
+    pub const SnapshotLead: BlockNumber = 2;
+
+    // Version 0 reported the only price, feed 0 of the price service, paying each oracle its fee
+    // without a budget.
+    pub const LegacyDuty: (oracle::ServiceId, oracle::DutyId) = (0, 0);
+    pub const LegacyServiceInfo: oracle::ServiceInfo<Balance> = oracle::ServiceInfo {
+        fee: 1 * DOLLARS,
+        miss_report_slash: 1 * DOLLARS,
+        reward_budget: Balance::max_value(),
+    };
 }
 
 impl oracle::Trait for Runtime {
@@ -289,20 +376,34 @@ impl oracle::Trait for Runtime {
 
     type Currency = Balances;
 
-    type OracleFee = OracleFee;
-    type MissReportSlash = MissReportSlash;
+    type MaxSlashPerEra = MaxSlashPerEra;
+    type MaxConsecutiveMisses = MaxConsecutiveMisses;
+    type JailCooldown = JailCooldown;
     type MinStaking = MinStaking;
 
     type MaliciousSlashOrigin =
         collective::EnsureProportionMoreThan<_1, _2, AccountId, OracleCollective>;
+    type ParametersOrigin = system::EnsureRoot<AccountId>;
 
     type Count = Count;
 
     type ReportInteval = ReportInteval;
     type ElectionEra = ElectionEra;
     type LockedDuration = LockedDuration;
+    type SlashDeferDuration = SlashDeferDuration;
 
     type ChangeMembers = OracleMembers;
+    type Duties = Price;
+
+    type ReputationDecay = ReputationDecay;
+    type ReputationWeight = ReputationWeight;
+    type RewardPolicy = oracle::AccuracyWeightedReward;
+    type HistoryDepth = HistoryDepth;
+    type MaxUnlockingChunks = MaxUnlockingChunks;
+    type SnapshotLead = SnapshotLead;
+    type KeySignature = Signature;
+    type LegacyDuty = LegacyDuty;
+    type LegacyServiceInfo = LegacyServiceInfo;
 }
 
 construct_runtime!(
@@ -318,9 +419,10 @@ construct_runtime!(
 		Indices: indices::{default, Config<T>},
 		Balances: balances::{default, Error},
 		Sudo: sudo,
-		Oracle: oracle::{Module, Call, Storage, Event<T>},
+		Oracle: oracle::{Module, Call, Storage, Event<T>, Config<T>},
                 OracleMembers: collective::<Instance1>::{Module, Call, Storage, Origin<T>, Event<T>},
-		Price: price::{Module, Call, Storage, Event<T>},
+		Price: price::{Module, Call, Storage, Event<T>, Config, ValidateUnsigned},
+		Attestation: attestation::{Module, Call, Storage, Event<T>},
 	}
 );
 
@@ -341,7 +443,7 @@ pub type SignedExtra = (
     system::CheckEra<Runtime>,
     system::CheckNonce<Runtime>,
     system::CheckWeight<Runtime>,
-    balances::TakeFees<Runtime>,
+    TakeFeesExceptOracles,
 );
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
@@ -394,6 +496,24 @@ impl_runtime_apis! {
         }
     }
 
+    impl oracle::OracleApi<Block, AccountId, Balance> for Runtime {
+        fn reputation(who: AccountId) -> oracle::Reputation {
+            Oracle::reputation(&who)
+        }
+
+        fn reputation_score(who: AccountId) -> Perbill {
+            Oracle::reputation(&who).score()
+        }
+
+        fn election_snapshot() -> Vec<(AccountId, Balance)> {
+            Oracle::snapshot()
+        }
+
+        fn expected_rank(who: AccountId) -> Option<u32> {
+            Oracle::expected_rank(&who)
+        }
+    }
+
     impl client_api::TaggedTransactionQueue<Block> for Runtime {
         fn validate_transaction(tx: <Block as BlockT>::Extrinsic) -> TransactionValidity {
             Executive::validate_transaction(tx)
@@ -7,6 +7,7 @@ const { ApiPromise, WsProvider } = require('@polkadot/api');
 const testKeyring = require('@polkadot/keyring/testing');
 const BN = require('bn.js');
 const [Alice, Charlie, BOB] = ["5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY", "5FLSigC9HGRKVhB9FiEo4Y3koPsNmBmLJbpXg2mp1hXcS59Y", "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty"]
+const BTC_USD = 0
 const snooze = ms => new Promise(resolve => setTimeout(resolve, ms));
 
 async function main() {
@@ -31,13 +32,100 @@ async function main() {
           "price": "Price",
         },
         "Price": "u128",
+        "ServiceId": "u32",
+        "DutyId": "u32",
+        "FeedId": "u32",
+        "RequestId": "u64",
         "Ledger": {
+          "stash": "AccountId",
           "active": "Balance",
           "unbonds": "Vec<Unbind>"
         },
         "Unbind": {
           "amount": "Balance",
           "era": "BlockNumber"
+        },
+        "EraIndex": "u32",
+        "OracleId": "AccountId",
+        "ServiceInfo": {
+          "fee": "Balance",
+          "miss_report_slash": "Balance",
+          "reward_budget": "Balance"
+        },
+        "OracleParameters": {
+          "min_staking": "Balance",
+          "count": "u16",
+          "report_interval": "BlockNumber",
+          "election_era": "BlockNumber",
+          "locked_duration": "BlockNumber"
+        },
+        "Performance": {
+          "reports": "u32",
+          "misses": "u32",
+          "paid": "Balance",
+          "slashed": "Balance"
+        },
+        "Reputation": {
+          "on_time": "Perbill",
+          "deviation": "u32",
+          "slashes": "u32"
+        },
+        "RewardDestination": {
+          "_enum": {
+            "Stash": "Null",
+            "Staked": "Null",
+            "Account": "AccountId"
+          }
+        },
+        "UnappliedSlash": {
+          "who": "AccountId",
+          "amount": "Balance",
+          "era": "BlockNumber"
+        },
+        "Round": "u64",
+        "Attestation": {
+          "feed": "FeedId",
+          "price": "Price",
+          "round": "Round"
+        },
+        "SignedAttestation": {
+          "attestation": "Attestation",
+          "signer": "AccountId",
+          "signature": "Signature"
+        },
+        "AggregateSignature": {
+          "signers": "Vec<u32>",
+          "signature": "H512"
+        },
+        "SubmissionMode": {
+          "_enum": ["Reports", "Threshold"]
+        },
+        "FeedPrice": {
+          "price": "Price",
+          "updated_at": "BlockNumber",
+          "timestamp": "Moment"
+        },
+        "UpdatePolicy": {
+          "deviation": "u32",
+          "heartbeat": "BlockNumber",
+          "circuit_breaker": "u32"
+        },
+        "PriceRequest": {
+          "requester": "AccountId",
+          "feed": "FeedId",
+          "fee": "Balance",
+          "deadline": "BlockNumber",
+          "answers": "Vec<PriceReport>"
+        },
+        "Request": {
+          "requester": "AccountId",
+          "spec": "Vec<u8>",
+          "bounty": "Balance",
+          "deadline": "BlockNumber"
+        },
+        "Answer": {
+          "reporter": "AccountId",
+          "payload": "Vec<u8>"
         }
       }
     })
@@ -48,7 +136,7 @@ async function main() {
     let ticker = await bitstamp.ticker(CURRENCY.BTC_USD);
     console.log("pushing", ticker.body.last)
     let price = new BN(new Decimal(ticker.body.last).mul(10000).toString())
-    let price_report = api.tx.price.report(price)
+    let price_report = api.tx.price.report(BTC_USD, price)
     await api.tx.oracleMembers.execute(price_report).signAndSend(sub_key, ({ events = [], status }) => {
       console.log("pushed price", price.toString(), status.toString(), status.toString())
     })
@@ -1,8 +1,10 @@
 use primitives::{Pair, Public};
 use node_template_runtime::{
 	AccountId, BabeConfig, BalancesConfig, GenesisConfig, GrandpaConfig,
-	SudoConfig, IndicesConfig, SystemConfig, WASM_BINARY, 
+	SudoConfig, IndicesConfig, SystemConfig, OracleConfig, PriceConfig, PriceServiceId, DOLLARS, WASM_BINARY, 
 };
+use node_template_runtime::oracle::ServiceInfo;
+use node_template_runtime::price::FeedId;
 use babe_primitives::{AuthorityId as BabeId};
 use grandpa_primitives::{AuthorityId as GrandpaId};
 use substrate_service;
@@ -10,6 +12,9 @@ use substrate_service;
 // Note this is the URL for the telemetry server
 //const STAGING_TELEMETRY_URL: &str = "wss://telemetry.polkadot.io/submit/";
 
+/// Price feed of BTC/USD, as reported by `scripts/reporters`.
+const BTC_USD_FEED: FeedId = 0;
+
 /// Specialized `ChainSpec`. This is a specialization of the general Substrate ChainSpec type.
 pub type ChainSpec = substrate_service::ChainSpec<GenesisConfig>;
 
@@ -131,5 +136,15 @@ fn testnet_genesis(initial_authorities: Vec<(AccountId, AccountId, GrandpaId, Ba
 		grandpa: Some(GrandpaConfig {
 			authorities: initial_authorities.iter().map(|x| (x.2.clone(), 1)).collect(),
 		}),
+		oracle: Some(OracleConfig {
+			services: vec![(PriceServiceId::get(), ServiceInfo {
+				fee: 1 * DOLLARS,
+				miss_report_slash: 1 * DOLLARS,
+				reward_budget: 100 * DOLLARS,
+			})],
+		}),
+		price: Some(PriceConfig {
+			feeds: vec![BTC_USD_FEED],
+		}),
 	}
 }